@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
-use tokio::process::Command as TokioCommand;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{ChildStdin, Command as TokioCommand};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::fs::File;
+use tokio::task::JoinHandle;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use crate::error::WhiplashError;
+use crate::git_worktree::{exclude_whiplash_dir, WHIPLASH_DIR};
 use anyhow::{Result, anyhow};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{oneshot, Mutex, RwLock};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Runtime, State, Wry};
+use regex::Regex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeTaskInfo {
@@ -16,16 +24,487 @@ pub struct ClaudeTaskInfo {
     pub status: String,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
-    pub output: Vec<String>,
+    /// The most recent `max_output_lines` lines of output.
+    pub output: VecDeque<String>,
+    /// Whether older lines have been dropped from `output`.
+    pub truncated: bool,
+    pub total_lines_seen: usize,
+    /// File the full, untruncated output is written to.
+    pub log_path: Option<String>,
     pub working_directory: String,
     pub worktree_name: String,
+    pub args: Vec<String>,
+    /// Kept in memory only so retries reuse it; never sent to the UI or written to disk.
+    #[serde(skip)]
+    pub env: HashMap<String, String>,
+    /// Names of the variables in `env`. Stored so a task loaded from disk knows it had an
+    /// environment that can't be restored.
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub idle_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    #[serde(default)]
+    pub load_dotenv: bool,
+    #[serde(default)]
+    pub interactive: bool,
+    /// Events parsed from `stream-json` output, capped like `output`.
+    #[serde(default)]
+    pub events: VecDeque<ClaudeEvent>,
+    /// The task this one re-runs, when started by `retry_claude_task` or auto-retry.
+    pub retry_of: Option<String>,
+    /// Position in the queue (0 is next) while the task is `"queued"`.
+    pub queue_position: Option<usize>,
+    /// Usage reported by the claude CLI. Only available when the task was started with
+    /// `--output-format json` or `stream-json`.
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cost_usd: Option<f64>,
+    /// Latest progress percentage (0-100) parsed from output via
+    /// `ClaudeRunnerConfig::progress_patterns`.
+    pub progress: Option<f32>,
+    /// Why the task failed, when output matched one of
+    /// `ClaudeRunnerConfig::failure_patterns`, e.g. `"rate_limit"` or `"auth"`.
+    pub failure_reason: Option<String>,
+    /// Exit code of the claude process. Unset while it runs, and for tasks that timed
+    /// out, were cancelled or were killed by a signal.
+    pub exit_code: Option<i32>,
+    /// The chain this task is a step of, when started by `start_claude_task_chain`.
+    pub chain_id: Option<String>,
+    /// 0-based position of this task in its chain.
+    pub step_index: Option<usize>,
+    /// Files the task changed in its worktree, committed or not, found by comparing git
+    /// status before and after it ran. Filled in once the task finishes.
+    #[serde(default)]
+    pub modified_files: Vec<String>,
+}
+
+impl ClaudeTaskInfo {
+    /// Appends a line of output, dropping the oldest lines beyond `max_lines`.
+    fn push_output(&mut self, line: String, max_lines: usize) {
+        self.output.push_back(line);
+        self.total_lines_seen += 1;
+        while self.output.len() > max_lines {
+            self.output.pop_front();
+            self.truncated = true;
+        }
+    }
+
+    /// A copy of the task without its output and events, which can run to thousands of
+    /// lines and are already in the log file. Used for the task store.
+    fn metadata(&mut self) -> Self {
+        let output = std::mem::take(&mut self.output);
+        let events = std::mem::take(&mut self.events);
+        let metadata = self.clone();
+        self.output = output;
+        self.events = events;
+        metadata
+    }
+
+    fn push_event(&mut self, event: ClaudeEvent, max_events: usize) {
+        self.events.push_back(event);
+        while self.events.len() > max_events {
+            self.events.pop_front();
+        }
+    }
+
+    /// Fills in token and cost usage from the CLI's final JSON result message, which is
+    /// the last output line carrying `usage` or `total_cost_usd`.
+    fn record_usage(&mut self) {
+        let result = self.output
+            .iter()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .find(|value| value.get("usage").is_some() || value.get("total_cost_usd").is_some());
+
+        if let Some(result) = result {
+            let usage = result.get("usage");
+            self.input_tokens = usage.and_then(|usage| usage.get("input_tokens")).and_then(|v| v.as_u64());
+            self.output_tokens = usage.and_then(|usage| usage.get("output_tokens")).and_then(|v| v.as_u64());
+            self.cost_usd = result.get("total_cost_usd").and_then(|v| v.as_f64());
+        }
+    }
+}
+
+/// Extracts a progress percentage from an output line using the first matching pattern.
+/// A pattern with two capture groups reads "step X of Y"; otherwise the first group is
+/// read as a percentage.
+fn parse_progress(patterns: &[Regex], line: &str) -> Option<f32> {
+    patterns.iter().find_map(|pattern| {
+        let captures = pattern.captures(line)?;
+        let first: f32 = captures.get(1)?.as_str().parse().ok()?;
+        let percent = match captures.get(2) {
+            Some(total) => {
+                let total: f32 = total.as_str().parse().ok()?;
+                if total <= 0.0 {
+                    return None;
+                }
+                first / total * 100.0
+            }
+            None => first,
+        };
+        Some(percent.clamp(0.0, 100.0))
+    })
+}
+
+/// Parses `.env` content: `KEY=value` lines, optionally prefixed with `export`. Values
+/// may be single-quoted (taken literally) or double-quoted (`\n`, `\"` and `\\` are
+/// unescaped); unquoted values end at a ` #` comment. Blank lines, comments and lines
+/// without `=` are skipped.
+fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('\'') {
+            quoted.split('\'').next().unwrap_or_default().to_string()
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            let mut unescaped = String::new();
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => match chars.next() {
+                        Some('n') => unescaped.push('\n'),
+                        Some(other) => unescaped.push(other),
+                        None => break,
+                    },
+                    c => unescaped.push(c),
+                }
+            }
+            unescaped
+        } else {
+            value.split(" #").next().unwrap_or_default().trim_end().to_string()
+        };
+
+        vars.push((key.to_string(), value));
+    }
+    vars
+}
+
+/// The dirty files of a worktree at one point in time, with enough detail to tell
+/// whether a file that was already dirty changed again.
+struct WorktreeSnapshot {
+    head: Option<git2::Oid>,
+    /// Where task logs are written. Left out of the snapshot, since every task writes
+    /// its own log there.
+    log_dir: PathBuf,
+    /// Status, mtime and size of each changed or untracked file.
+    files: HashMap<String, (git2::Status, Option<std::time::SystemTime>, Option<u64>)>,
+}
+
+impl WorktreeSnapshot {
+    fn capture(working_directory: &Path, log_dir: PathBuf) -> Result<Self> {
+        let repo = git2::Repository::open(working_directory)?;
+        let head = repo.head().ok().and_then(|head| head.target());
+
+        let mut status_options = git2::StatusOptions::new();
+        status_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+
+        let mut files = HashMap::new();
+        for entry in repo.statuses(Some(&mut status_options))?.iter() {
+            if let Some(path) = entry.path() {
+                if working_directory.join(path).starts_with(&log_dir) {
+                    continue;
+                }
+                let metadata = std::fs::metadata(working_directory.join(path)).ok();
+                files.insert(
+                    path.to_string(),
+                    (
+                        entry.status(),
+                        metadata.as_ref().and_then(|metadata| metadata.modified().ok()),
+                        metadata.map(|metadata| metadata.len()),
+                    ),
+                );
+            }
+        }
+
+        Ok(Self { head, log_dir, files })
+    }
+
+    /// Files that differ between this snapshot and a later one: newly dirty, changed
+    /// again, reverted, or touched by commits made in between.
+    fn modified_files(&self, working_directory: &Path) -> Result<Vec<String>> {
+        let after = Self::capture(working_directory, self.log_dir.clone())?;
+        let mut modified: std::collections::BTreeSet<String> = after.files
+            .iter()
+            .filter(|(path, state)| self.files.get(*path) != Some(*state))
+            .map(|(path, _)| path.clone())
+            .collect();
+        modified.extend(self.files.keys().filter(|path| !after.files.contains_key(*path)).cloned());
+
+        if let (Some(before_head), Some(after_head)) = (self.head, after.head) {
+            if before_head != after_head {
+                let repo = git2::Repository::open(working_directory)?;
+                let before_tree = repo.find_commit(before_head)?.tree()?;
+                let after_tree = repo.find_commit(after_head)?.tree()?;
+                let diff = repo.diff_tree_to_tree(Some(&before_tree), Some(&after_tree), None)?;
+                for delta in diff.deltas() {
+                    for file in [delta.old_file(), delta.new_file()] {
+                        if let Some(path) = file.path() {
+                            modified.insert(path.to_string_lossy().to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(modified.into_iter().collect())
+    }
 }
 
+/// Whether the args ask the claude CLI for JSON output, which includes usage and cost.
+fn uses_json_output(args: &[String]) -> bool {
+    args.iter().enumerate().any(|(index, arg)| {
+        let format = match arg.strip_prefix("--output-format=") {
+            Some(format) => Some(format),
+            None if arg == "--output-format" => args.get(index + 1).map(String::as_str),
+            None => None,
+        };
+        matches!(format, Some("json") | Some("stream-json"))
+    })
+}
+
+/// Optional per-task settings supplied when starting a task.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClaudeTaskOptions {
+    /// Extra arguments passed to the claude command before the prompt, e.g. `--model`.
+    pub args: Vec<String>,
+    /// Environment variables for the claude process. These are merged on top of the
+    /// environment inherited from the app, overriding any variables with the same name.
+    pub env: HashMap<String, String>,
+    /// Overrides `ClaudeRunnerConfig::timeout_seconds` for this task.
+    pub timeout_seconds: Option<u64>,
+    /// Overrides `ClaudeRunnerConfig::idle_timeout_seconds` for this task.
+    pub idle_timeout_seconds: Option<u64>,
+    pub output_format: OutputFormat,
+    /// Load `.env` from the working directory into the process environment. Variables
+    /// in `env` take precedence over the file.
+    pub load_dotenv: bool,
+    /// Keep the process's stdin open so `send_claude_task_input` can answer prompts.
+    /// Otherwise stdin is empty, so a run that reads it doesn't wait for input.
+    pub interactive: bool,
+}
+
+/// One step of a task chain started by `start_claude_task_chain`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStep {
+    pub task_description: String,
+    #[serde(default)]
+    pub options: ClaudeTaskOptions,
+}
+
+/// How the claude CLI's stdout is interpreted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// Lines are kept as plain text.
+    #[default]
+    Text,
+    /// The CLI is run with `--output-format stream-json` and each line is also parsed
+    /// into a `ClaudeEvent`.
+    StreamJson,
+}
+
+/// One event from the CLI's `stream-json` output, e.g. an assistant message or a result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeEvent {
+    /// The event's `type`: `"system"`, `"assistant"`, `"user"` or `"result"`.
+    pub event_type: String,
+    pub subtype: Option<String>,
+    /// Names of the tools an assistant message calls, so the UI can show tool use.
+    pub tool_uses: Vec<String>,
+    /// The event exactly as the CLI emitted it.
+    pub raw: serde_json::Value,
+}
+
+impl ClaudeEvent {
+    /// Parses a line of `stream-json` output. Returns `None` for anything that isn't a
+    /// JSON object with a `type`, which then stays plain text.
+    fn parse(line: &str) -> Option<Self> {
+        let raw: serde_json::Value = serde_json::from_str(line).ok()?;
+        let event_type = raw.get("type")?.as_str()?.to_string();
+        let subtype = raw.get("subtype").and_then(|v| v.as_str()).map(str::to_string);
+        let tool_uses = raw.pointer("/message/content")
+            .and_then(|content| content.as_array())
+            .map(|blocks| {
+                blocks.iter()
+                    .filter(|block| block.get("type").and_then(|v| v.as_str()) == Some("tool_use"))
+                    .filter_map(|block| block.get("name").and_then(|v| v.as_str()).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self { event_type, subtype, tool_uses, raw })
+    }
+}
+
+/// Error returned when a task exceeds its timeout, so it can be reported as `"timed_out"`.
+#[derive(Debug)]
+enum TaskTimedOut {
+    /// Ran for longer than `timeout_seconds`.
+    Total(u64),
+    /// Printed nothing for `idle_timeout_seconds`.
+    Idle(u64),
+}
+
+impl std::fmt::Display for TaskTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskTimedOut::Total(seconds) => write!(f, "Claude command timed out after {} seconds", seconds),
+            TaskTimedOut::Idle(seconds) => write!(f, "Claude command produced no output for {} seconds", seconds),
+        }
+    }
+}
+
+impl std::error::Error for TaskTimedOut {}
+
+/// Error returned when the claude process exits unsuccessfully, keeping its exit code.
+#[derive(Debug)]
+struct TaskFailed(Option<i32>);
+
+impl std::fmt::Display for TaskFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Claude command failed with exit code: {}", self.0.unwrap_or(-1))
+    }
+}
+
+impl std::error::Error for TaskFailed {}
+
+/// A slice of a task's output, used by the UI to append only new lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeTaskOutputChunk {
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+}
+
+/// Payload of the `claude-task-completed` event, emitted once when a task finishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeTaskCompletedEvent {
+    pub task_id: String,
+    pub status: String,
+    pub worktree_name: String,
+    pub duration_secs: Option<f64>,
+    /// `None` if the process was killed or never started.
+    pub exit_code: Option<i32>,
+}
+
+impl ClaudeTaskCompletedEvent {
+    fn new(task: &ClaudeTaskInfo) -> Self {
+        Self {
+            task_id: task.id.clone(),
+            status: task.status.clone(),
+            worktree_name: task.worktree_name.clone(),
+            duration_secs: task.started_at
+                .zip(task.completed_at)
+                .map(|(started_at, completed_at)| (completed_at - started_at).num_milliseconds() as f64 / 1000.0),
+            exit_code: task.exit_code,
+        }
+    }
+}
+
+/// Result of `check_claude_available`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeAvailability {
+    pub available: bool,
+    pub version: Option<String>,
+    /// Where `claude_command` resolved to on `PATH`.
+    pub path: Option<String>,
+    /// Why the command isn't usable, when it isn't.
+    pub error: Option<String>,
+}
+
+/// Filters and pagination for `list_claude_tasks`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClaudeTaskQuery {
+    /// Only include tasks with one of these statuses.
+    pub status_filter: Option<Vec<String>>,
+    pub worktree_name: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Include each task's output and events. Off by default to keep the list lightweight;
+    /// use `get_claude_task_status` to fetch a single task's output.
+    pub include_output: bool,
+}
+
+/// A page of tasks along with the number of tasks matching the query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeTaskPage {
+    pub tasks: Vec<ClaudeTaskInfo>,
+    pub total: usize,
+    /// The scheduler is paused, so queued tasks won't start until it's resumed.
+    pub paused: bool,
+}
+
+/// Payload of the `claude-task-output` event, emitted for every line the process prints.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeTaskOutputEvent {
+    pub task_id: String,
+    pub line: String,
+    pub stream: String, // "stdout", "stderr"
+}
+
+/// Payload of the `claude-task-status` event, emitted whenever a task changes status.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeTaskStatusEvent {
+    pub task_id: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ClaudeRunnerConfig {
     pub claude_command: String,
     pub max_concurrent_tasks: usize,
+    /// Hard cap on a task's run time. 0 removes the cap, leaving only the idle timeout.
     pub timeout_seconds: u64,
+    /// Kill a task once it has printed nothing for this long. Output on either stream
+    /// resets the timer. `None` disables the idle timeout.
+    pub idle_timeout_seconds: Option<u64>,
+    /// Reject new tasks when `max_concurrent_tasks` are running instead of queueing them.
+    pub reject_when_full: bool,
+    /// Number of output lines kept in memory per task.
+    pub max_output_lines: usize,
+    /// Directory for per-task log files. Defaults to `<working_directory>/.whiplash/logs`.
+    pub log_dir: Option<PathBuf>,
+    /// Regexes matched against output lines to track progress, e.g. `(\d+)%` or
+    /// `step (\d+) of (\d+)`. Empty by default so progress stays `None`.
+    pub progress_patterns: Vec<String>,
+    /// How many times a failed task is retried automatically. 0 disables auto-retry.
+    pub auto_retry_count: u32,
+    /// Delay before the first automatic retry, doubled for each further attempt.
+    pub auto_retry_backoff_seconds: u64,
+    /// Case-insensitive regexes, keyed by failure reason, matched against output lines.
+    /// The first reason to match becomes the task's `failure_reason` if it fails.
+    pub failure_patterns: BTreeMap<String, Vec<String>>,
+    /// How long `cleanup_completed_tasks` keeps finished tasks, keyed by status, e.g.
+    /// `{"completed": 600, "failed": 86400}`. Statuses not listed use
+    /// `default_retention_seconds`.
+    pub retention_seconds: BTreeMap<String, u64>,
+    pub default_retention_seconds: u64,
+    /// Most finished tasks to keep; cleanup drops the oldest beyond this, whatever their age.
+    pub max_tasks: Option<usize>,
 }
 
 impl Default for ClaudeRunnerConfig {
@@ -34,287 +513,2072 @@ impl Default for ClaudeRunnerConfig {
             claude_command: "claude".to_string(),
             max_concurrent_tasks: 3,
             timeout_seconds: 3600, // 1 hour
+            idle_timeout_seconds: None,
+            reject_when_full: false,
+            max_output_lines: 10_000,
+            log_dir: None,
+            progress_patterns: Vec::new(),
+            auto_retry_count: 0,
+            auto_retry_backoff_seconds: 10,
+            failure_patterns: BTreeMap::from([
+                (
+                    "rate_limit".to_string(),
+                    vec![r"\b429\b".to_string(), r"rate.?limit".to_string(), r"too many requests".to_string()],
+                ),
+                (
+                    "auth".to_string(),
+                    vec![
+                        r"\b401\b".to_string(),
+                        r"authentication".to_string(),
+                        r"invalid.?api.?key".to_string(),
+                        r"unauthorized".to_string(),
+                    ],
+                ),
+            ]),
+            retention_seconds: BTreeMap::new(),
+            default_retention_seconds: 3600, // 1 hour
+            max_tasks: None,
+        }
+    }
+}
+
+impl ClaudeRunnerConfig {
+    /// Reads the config from a JSON file, falling back to the defaults for a missing or
+    /// unreadable file and for any settings it leaves out, then applies env overrides.
+    /// Invalid settings are replaced by their defaults, with a warning.
+    pub fn load(path: &Path) -> Self {
+        let mut config = Self::read_file(path);
+        EnvOverrides::from_env().apply(&mut config);
+
+        if let Err(e) = config.validate() {
+            eprintln!("Ignoring invalid runner config in '{}': {}", path.display(), e);
+            config.max_concurrent_tasks = Self::default().max_concurrent_tasks;
+        }
+        config
+    }
+
+    /// The config as saved in the file, without env overrides.
+    fn read_file(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.max_concurrent_tasks == 0 {
+            return Err(WhiplashError::InvalidInput("max_concurrent_tasks must be at least 1".to_string()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Settings given through env vars, which take precedence over the config file.
+struct EnvOverrides {
+    claude_command: Option<String>,
+    max_concurrent_tasks: Option<usize>,
+    timeout_seconds: Option<u64>,
+    idle_timeout_seconds: Option<u64>,
+}
+
+impl EnvOverrides {
+    /// Reads `WHIPLASH_CLAUDE_COMMAND`, `WHIPLASH_MAX_CONCURRENT_TASKS`,
+    /// `WHIPLASH_TIMEOUT_SECONDS` and `WHIPLASH_IDLE_TIMEOUT_SECONDS`. Unparseable values,
+    /// and a max of 0 concurrent tasks, are ignored.
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+
+        Self {
+            claude_command: var("WHIPLASH_CLAUDE_COMMAND"),
+            max_concurrent_tasks: var("WHIPLASH_MAX_CONCURRENT_TASKS")
+                .and_then(|value| value.trim().parse().ok())
+                .filter(|&max_concurrent_tasks| max_concurrent_tasks > 0),
+            timeout_seconds: var("WHIPLASH_TIMEOUT_SECONDS").and_then(|value| value.trim().parse().ok()),
+            idle_timeout_seconds: var("WHIPLASH_IDLE_TIMEOUT_SECONDS").and_then(|value| value.trim().parse().ok()),
+        }
+    }
+
+    fn apply(&self, config: &mut ClaudeRunnerConfig) {
+        if let Some(claude_command) = &self.claude_command {
+            config.claude_command = claude_command.clone();
+        }
+        if let Some(max_concurrent_tasks) = self.max_concurrent_tasks {
+            config.max_concurrent_tasks = max_concurrent_tasks;
+        }
+        if let Some(timeout_seconds) = self.timeout_seconds {
+            config.timeout_seconds = timeout_seconds;
+        }
+        if let Some(idle_timeout_seconds) = self.idle_timeout_seconds {
+            config.idle_timeout_seconds = Some(idle_timeout_seconds);
+        }
+    }
+
+    /// Puts `saved`'s value back for every overridden setting, so that saving a config
+    /// never writes env values into the file.
+    fn revert(&self, config: &mut ClaudeRunnerConfig, saved: &ClaudeRunnerConfig) {
+        if self.claude_command.is_some() {
+            config.claude_command = saved.claude_command.clone();
+        }
+        if self.max_concurrent_tasks.is_some() {
+            config.max_concurrent_tasks = saved.max_concurrent_tasks;
+        }
+        if self.timeout_seconds.is_some() {
+            config.timeout_seconds = saved.timeout_seconds;
+        }
+        if self.idle_timeout_seconds.is_some() {
+            config.idle_timeout_seconds = saved.idle_timeout_seconds;
+        }
+    }
+}
+
+/// A config together with its compiled patterns. Replaced whole by `update_config`, so
+/// a running task keeps the settings it started with.
+struct RunnerSettings {
+    config: ClaudeRunnerConfig,
+    /// Compiled `config.progress_patterns`; invalid patterns are skipped.
+    progress_patterns: Vec<Regex>,
+    /// Compiled `config.failure_patterns`, paired with their reason.
+    failure_patterns: Vec<(String, Regex)>,
+}
+
+impl RunnerSettings {
+    fn new(config: ClaudeRunnerConfig) -> Self {
+        let progress_patterns = config.progress_patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+        let failure_patterns = config.failure_patterns
+            .iter()
+            .flat_map(|(reason, patterns)| {
+                patterns.iter().filter_map(move |pattern| {
+                    Regex::new(&format!("(?i){}", pattern)).ok().map(|regex| (reason.clone(), regex))
+                })
+            })
+            .collect();
+
+        Self {
+            config,
+            progress_patterns,
+            failure_patterns,
+        }
+    }
+}
+
+/// Handle to a spawned claude process, kept outside `ClaudeTaskInfo` since it isn't serializable.
+struct ProcessHandle {
+    /// Asks the runner to kill the process; the inner sender is signalled once it has exited.
+    cancel: oneshot::Sender<oneshot::Sender<()>>,
+    /// The process's stdin, for answering prompts while it runs.
+    stdin: Option<Arc<Mutex<ChildStdin>>>,
+}
+
+type ProcessMap = Arc<Mutex<HashMap<String, ProcessHandle>>>;
+
+enum ProcessOutcome {
+    Exited(std::io::Result<std::process::ExitStatus>),
+    TimedOut(TaskTimedOut),
+    Cancelled(oneshot::Sender<()>),
+}
+
+/// Output lines are applied to the task map once this many are waiting...
+const OUTPUT_FLUSH_LINES: usize = 64;
+/// ...or once the oldest waiting line is this old.
+const OUTPUT_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Task store writes are held back this long, so a burst of transitions is saved once.
+const STORE_FLUSH_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Output read from a process but not yet applied to its task.
+#[derive(Default)]
+struct OutputBatch {
+    entries: Vec<String>,
+    events: Vec<ClaudeEvent>,
+    progress: Option<f32>,
+    failure_reason: Option<String>,
+    first_line_at: Option<std::time::Instant>,
+}
+
+impl OutputBatch {
+    fn push(&mut self, entry: String, progress: Option<f32>, failure_reason: Option<String>) {
+        self.entries.push(entry);
+        if progress.is_some() {
+            self.progress = progress;
+        }
+        if self.failure_reason.is_none() {
+            self.failure_reason = failure_reason;
+        }
+        self.first_line_at.get_or_insert_with(std::time::Instant::now);
+    }
+
+    /// Writes the batch to the log and the task with one lock acquisition each.
+    async fn flush<R: Runtime>(
+        &mut self,
+        app: &AppHandle<R>,
+        task_id: &str,
+        active_tasks: &RwLock<HashMap<String, ClaudeTaskInfo>>,
+        log_file: &Mutex<File>,
+        max_output_lines: usize,
+    ) {
+        let batch = std::mem::take(self);
+        if batch.entries.is_empty() {
+            return;
+        }
+
+        // The log keeps everything, even once the in-memory buffer is truncated
+        let mut text = batch.entries.join("\n");
+        text.push('\n');
+        let _ = log_file.lock().await.write_all(text.as_bytes()).await;
+
+        let status = {
+            let mut tasks = active_tasks.write().await;
+            tasks.get_mut(task_id).map(|task| {
+                for entry in batch.entries {
+                    task.push_output(entry, max_output_lines);
+                }
+                for event in batch.events {
+                    task.push_event(event, max_output_lines);
+                }
+                if batch.progress.is_some() {
+                    task.progress = batch.progress;
+                }
+                if task.failure_reason.is_none() {
+                    task.failure_reason = batch.failure_reason;
+                }
+                task.status.clone()
+            })
+        };
+
+        if let (Some(status), Some(progress)) = (status, batch.progress) {
+            emit_status_with_progress(app, task_id, &status, Some(progress));
+        }
+    }
+}
+
+/// A task waiting for a free slot, along with what's needed to start it later.
+struct QueuedTask<R: Runtime> {
+    task_id: String,
+    app: AppHandle<R>,
+    options: ClaudeTaskOptions,
+}
+
+/// A chain of steps run one after another in the same worktree. Removed from the runner
+/// once its last step has finished or the chain has stopped.
+struct TaskChain<R: Runtime> {
+    app: AppHandle<R>,
+    worktree_name: String,
+    working_directory: String,
+    steps: Vec<TaskStep>,
+}
+
+/// Generic over the Tauri runtime so tests can drive it with a mock app.
+pub struct ClaudeRunner<R: Runtime = Wry> {
+    settings: Arc<std::sync::RwLock<Arc<RunnerSettings>>>,
+    /// JSON file `update_config` saves the config to.
+    config_path: Option<PathBuf>,
+    active_tasks: Arc<RwLock<HashMap<String, ClaudeTaskInfo>>>,
+    processes: ProcessMap,
+    queue: Arc<Mutex<VecDeque<QueuedTask<R>>>>,
+    chains: Arc<Mutex<HashMap<String, TaskChain<R>>>>,
+    /// While set, new tasks are queued even when there are free slots.
+    paused: Arc<AtomicBool>,
+    /// JSON file tasks are persisted to so history survives restarts.
+    store_path: Option<PathBuf>,
+    /// Serializes writes to `store_path` so an older snapshot can't overwrite a newer one.
+    store_lock: Arc<Mutex<()>>,
+    /// Set while tasks have changed since the store was last written; a flush is
+    /// scheduled whenever it goes from unset to set.
+    store_dirty: Arc<AtomicBool>,
+}
+
+// Derived Clone would require the runtime itself to be Clone
+impl<R: Runtime> Clone for ClaudeRunner<R> {
+    fn clone(&self) -> Self {
+        Self {
+            settings: self.settings.clone(),
+            config_path: self.config_path.clone(),
+            active_tasks: self.active_tasks.clone(),
+            processes: self.processes.clone(),
+            queue: self.queue.clone(),
+            chains: self.chains.clone(),
+            paused: self.paused.clone(),
+            store_path: self.store_path.clone(),
+            store_lock: self.store_lock.clone(),
+            store_dirty: self.store_dirty.clone(),
+        }
+    }
+}
+
+impl<R: Runtime> ClaudeRunner<R> {
+    pub fn new(config: ClaudeRunnerConfig) -> Self {
+        Self {
+            settings: Arc::new(std::sync::RwLock::new(Arc::new(RunnerSettings::new(config)))),
+            config_path: None,
+            active_tasks: Arc::new(RwLock::new(HashMap::new())),
+            processes: Arc::new(Mutex::new(HashMap::new())),
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+            chains: Arc::new(Mutex::new(HashMap::new())),
+            paused: Arc::new(AtomicBool::new(false)),
+            store_path: None,
+            store_lock: Arc::new(Mutex::new(())),
+            store_dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Saves config changes made through `update_config` to `config_path`.
+    pub fn with_config_path(mut self, config_path: PathBuf) -> Self {
+        self.config_path = Some(config_path);
+        self
+    }
+
+    fn settings(&self) -> Arc<RunnerSettings> {
+        self.settings.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn config(&self) -> ClaudeRunnerConfig {
+        self.settings().config.clone()
+    }
+
+    /// Replaces the config and saves it to `config_path`. New limits apply to tasks
+    /// started from now on; a higher `max_concurrent_tasks` starts queued tasks straight away.
+    /// Settings overridden by env vars keep their env value and aren't saved.
+    pub async fn update_config(&self, mut config: ClaudeRunnerConfig) -> Result<()> {
+        config.validate()?;
+        let env_overrides = EnvOverrides::from_env();
+
+        if let Some(config_path) = &self.config_path {
+            let mut saved_config = config.clone();
+            env_overrides.revert(&mut saved_config, &ClaudeRunnerConfig::read_file(config_path));
+            if let Some(parent) = config_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(config_path, serde_json::to_string_pretty(&saved_config)?).await?;
+        }
+
+        env_overrides.apply(&mut config);
+
+        *self.settings.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(RunnerSettings::new(config));
+        self.dispatch_queued().await;
+        Ok(())
+    }
+
+    /// Persists tasks to `store_path`, loading any tasks saved by a previous run.
+    /// Tasks that hadn't finished are marked `"interrupted"` since their processes are gone.
+    /// Only metadata is stored, so loaded tasks have no output beyond their log file.
+    pub fn with_store(mut self, store_path: PathBuf) -> Self {
+        let mut tasks: HashMap<String, ClaudeTaskInfo> = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Vec<ClaudeTaskInfo>>(&contents).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|task| (task.id.clone(), task))
+            .collect();
+
+        let now = Utc::now();
+        for task in tasks.values_mut() {
+            if matches!(task.status.as_str(), "queued" | "pending" | "running") {
+                task.status = "interrupted".to_string();
+                task.completed_at = Some(now);
+            }
+            task.queue_position = None;
+            task.truncated = task.total_lines_seen > 0;
+        }
+
+        self.active_tasks = Arc::new(RwLock::new(tasks));
+        self.store_path = Some(store_path);
+        self
+    }
+
+    /// Schedules a write of every task's metadata to the store, if one is configured.
+    /// Called after each status transition; transitions within `STORE_FLUSH_DELAY` of
+    /// each other share a single write.
+    fn persist(&self) {
+        if self.store_path.is_none() || self.store_dirty.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let runner = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(STORE_FLUSH_DELAY).await;
+            runner.flush_store().await;
+        });
+    }
+
+    /// Writes the store now. Failures are ignored so a bad disk doesn't stop tasks.
+    async fn flush_store(&self) {
+        let store_path = match &self.store_path {
+            Some(store_path) => store_path,
+            None => return,
+        };
+
+        let _guard = self.store_lock.lock().await;
+        // Cleared before the snapshot, so any change after it schedules another write
+        self.store_dirty.store(false, Ordering::SeqCst);
+        let snapshot: Vec<ClaudeTaskInfo> = self.active_tasks.write().await
+            .values_mut()
+            .map(ClaudeTaskInfo::metadata)
+            .collect();
+        let _ = write_task_store(store_path, &snapshot).await;
+    }
+
+    pub async fn start_task(
+        &self,
+        app: &AppHandle<R>,
+        worktree_name: &str,
+        working_directory: &str,
+        task_description: &str,
+        options: ClaudeTaskOptions,
+    ) -> Result<String> {
+        self.start_task_with_origin(app, worktree_name, working_directory, task_description, options, None, None).await
+    }
+
+    /// Runs `steps` one after another in the same worktree, each starting only once the
+    /// previous one has completed. If a step doesn't complete, the chain stops and the
+    /// remaining steps are recorded as `"skipped"`. Returns the chain id.
+    pub async fn start_task_chain(
+        &self,
+        app: &AppHandle<R>,
+        worktree_name: &str,
+        working_directory: &str,
+        steps: Vec<TaskStep>,
+    ) -> Result<String> {
+        if steps.is_empty() {
+            return Err(WhiplashError::InvalidInput("A task chain needs at least one step".to_string()).into());
+        }
+
+        let chain_id = Uuid::new_v4().to_string();
+        self.chains.lock().await.insert(chain_id.clone(), TaskChain {
+            app: app.clone(),
+            worktree_name: worktree_name.to_string(),
+            working_directory: working_directory.to_string(),
+            steps,
+        });
+
+        if let Err(e) = self.start_chain_step(&chain_id, 0).await {
+            self.chains.lock().await.remove(&chain_id);
+            return Err(e);
+        }
+        Ok(chain_id)
+    }
+
+    async fn start_chain_step(&self, chain_id: &str, step_index: usize) -> Result<String> {
+        let (app, worktree_name, working_directory, step) = {
+            let chains = self.chains.lock().await;
+            let chain = chains.get(chain_id).ok_or_else(|| anyhow!("Task chain not found"))?;
+            let step = chain.steps.get(step_index).cloned().ok_or_else(|| anyhow!("Task chain step not found"))?;
+            (chain.app.clone(), chain.worktree_name.clone(), chain.working_directory.clone(), step)
+        };
+
+        self.start_task_with_origin(
+            &app,
+            &worktree_name,
+            &working_directory,
+            &step.task_description,
+            step.options,
+            None,
+            Some((chain_id.to_string(), step_index)),
+        ).await
+    }
+
+    /// Called when a chain step finishes: starts the next step if this one completed,
+    /// otherwise stops the chain.
+    async fn advance_chain(&self, chain_id: &str, step_index: usize, status: &str) {
+        let next_step = step_index + 1;
+        let has_next = self.chains.lock().await
+            .get(chain_id)
+            .is_some_and(|chain| next_step < chain.steps.len());
+
+        if status == "completed" && has_next && self.start_chain_step(chain_id, next_step).await.is_ok() {
+            return;
+        }
+        self.stop_chain(chain_id, next_step).await;
+    }
+
+    /// Removes a chain, recording its steps from `from_step` on as `"skipped"`.
+    async fn stop_chain(&self, chain_id: &str, from_step: usize) {
+        let chain = match self.chains.lock().await.remove(chain_id) {
+            Some(chain) => chain,
+            None => return,
+        };
+
+        let skipped: Vec<String> = {
+            let mut tasks = self.active_tasks.write().await;
+            let now = Utc::now();
+            chain.steps.iter()
+                .enumerate()
+                .skip(from_step)
+                .map(|(step_index, step)| {
+                    let mut task = self.new_task_info(
+                        &chain.worktree_name,
+                        &chain.working_directory,
+                        &step.task_description,
+                        &step.options,
+                        None,
+                        Some((chain_id.to_string(), step_index)),
+                    );
+                    task.status = "skipped".to_string();
+                    task.started_at = None;
+                    task.completed_at = Some(now);
+                    let task_id = task.id.clone();
+                    tasks.insert(task_id.clone(), task);
+                    task_id
+                })
+                .collect()
+        };
+
+        for task_id in &skipped {
+            emit_status(&chain.app, task_id, "skipped");
+        }
+        self.persist();
+    }
+
+    /// Cancels a chain's current step and skips the ones after it. Returns the number of
+    /// steps that were skipped.
+    pub async fn cancel_task_chain(&self, app: &AppHandle<R>, chain_id: &str) -> Result<usize> {
+        let step_count = self.chains.lock().await
+            .get(chain_id)
+            .map(|chain| chain.steps.len())
+            .ok_or_else(|| WhiplashError::NotFound(format!("No running task chain '{}'", chain_id)))?;
+
+        // The current step is the latest one started
+        let current = {
+            let tasks = self.active_tasks.read().await;
+            tasks.values()
+                .filter(|task| task.chain_id.as_deref() == Some(chain_id))
+                .filter_map(|task| task.step_index.map(|step_index| (step_index, task.id.clone(), task.status.clone())))
+                .max_by_key(|(step_index, _, _)| *step_index)
+        };
+
+        let from_step = current.as_ref().map_or(0, |(step_index, _, _)| step_index + 1);
+        self.stop_chain(chain_id, from_step).await;
+
+        if let Some((_, task_id, status)) = current {
+            if matches!(status.as_str(), "queued" | "pending" | "running") {
+                self.cancel_task(app, &task_id).await?;
+            }
         }
+
+        Ok(step_count.saturating_sub(from_step))
+    }
+
+    /// Starts a fresh task with the same description, args, env, working directory and
+    /// worktree as `task_id`, linked to it through `retry_of`. Tasks that had an env and
+    /// were loaded from the store can't be retried, since their env wasn't saved.
+    pub async fn retry_task(&self, app: &AppHandle<R>, task_id: &str) -> Result<String> {
+        let original = {
+            let tasks = self.active_tasks.read().await;
+            tasks.get(task_id).cloned().ok_or_else(|| anyhow!("Task not found"))?
+        };
+        if matches!(original.status.as_str(), "queued" | "pending" | "running") {
+            return Err(anyhow!("Task is still active"));
+        }
+        // Env values aren't stored, so a task loaded from disk can't be re-run faithfully
+        if original.env.is_empty() && !original.env_keys.is_empty() {
+            return Err(WhiplashError::InvalidInput(format!(
+                "Task was restored without its environment ({}); start it again instead",
+                original.env_keys.join(", ")
+            )).into());
+        }
+
+        let options = ClaudeTaskOptions {
+            args: original.args,
+            env: original.env,
+            timeout_seconds: Some(original.timeout_seconds),
+            idle_timeout_seconds: original.idle_timeout_seconds,
+            output_format: original.output_format,
+            load_dotenv: original.load_dotenv,
+            interactive: original.interactive,
+        };
+        self.start_task_with_origin(
+            app,
+            &original.worktree_name,
+            &original.working_directory,
+            &original.description,
+            options,
+            Some(task_id.to_string()),
+            None,
+        ).await
+    }
+
+    /// Number of earlier attempts in a task's retry chain.
+    async fn retry_depth(&self, task_id: &str) -> u32 {
+        let tasks = self.active_tasks.read().await;
+        let mut depth = 0;
+        let mut current = tasks.get(task_id).and_then(|task| task.retry_of.as_ref());
+        while let Some(previous) = current {
+            depth += 1;
+            current = tasks.get(previous).and_then(|task| task.retry_of.as_ref());
+        }
+        depth
+    }
+
+    /// A new `"pending"` task with a fresh id, not yet added to `active_tasks`.
+    fn new_task_info(
+        &self,
+        worktree_name: &str,
+        working_directory: &str,
+        task_description: &str,
+        options: &ClaudeTaskOptions,
+        retry_of: Option<String>,
+        chain: Option<(String, usize)>,
+    ) -> ClaudeTaskInfo {
+        let (chain_id, step_index) = chain.unzip();
+        let settings = self.settings();
+        let config = &settings.config;
+        ClaudeTaskInfo {
+            id: Uuid::new_v4().to_string(),
+            description: task_description.to_string(),
+            status: "pending".to_string(),
+            started_at: Some(Utc::now()),
+            completed_at: None,
+            output: VecDeque::new(),
+            truncated: false,
+            total_lines_seen: 0,
+            log_path: None,
+            working_directory: working_directory.to_string(),
+            worktree_name: worktree_name.to_string(),
+            args: options.args.clone(),
+            env: options.env.clone(),
+            env_keys: {
+                let mut env_keys: Vec<String> = options.env.keys().cloned().collect();
+                env_keys.sort();
+                env_keys
+            },
+            timeout_seconds: options.timeout_seconds.unwrap_or(config.timeout_seconds),
+            idle_timeout_seconds: options.idle_timeout_seconds.or(config.idle_timeout_seconds),
+            output_format: options.output_format,
+            load_dotenv: options.load_dotenv,
+            interactive: options.interactive,
+            events: VecDeque::new(),
+            retry_of,
+            queue_position: None,
+            input_tokens: None,
+            output_tokens: None,
+            cost_usd: None,
+            progress: None,
+            failure_reason: None,
+            exit_code: None,
+            chain_id,
+            step_index,
+            modified_files: Vec::new(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn start_task_with_origin(
+        &self,
+        app: &AppHandle<R>,
+        worktree_name: &str,
+        working_directory: &str,
+        task_description: &str,
+        options: ClaudeTaskOptions,
+        retry_of: Option<String>,
+        chain: Option<(String, usize)>,
+    ) -> Result<String> {
+        let mut task_info = self.new_task_info(
+            worktree_name,
+            working_directory,
+            task_description,
+            &options,
+            retry_of,
+            chain,
+        );
+        let task_id = task_info.id.clone();
+
+        // Check for a free slot and claim it under one write lock, so concurrent starts
+        // can't all see the same free slot before any of them is inserted
+        {
+            let mut tasks = self.active_tasks.write().await;
+            let mut queue = self.queue.lock().await;
+            let settings = self.settings();
+            let config = &settings.config;
+            let at_capacity = active_count(&tasks) >= config.max_concurrent_tasks;
+            let paused = self.paused.load(Ordering::SeqCst);
+
+            // Anything already waiting goes first
+            if paused || at_capacity || !queue.is_empty() {
+                if config.reject_when_full && !paused {
+                    return Err(anyhow!("Maximum concurrent tasks reached"));
+                }
+
+                // Park the task until a running one finishes
+                task_info.status = "queued".to_string();
+                tasks.insert(task_id.clone(), task_info);
+                queue.push_back(QueuedTask {
+                    task_id: task_id.clone(),
+                    app: app.clone(),
+                    options,
+                });
+                drop(queue);
+                drop(tasks);
+
+                emit_status(app, &task_id, "queued");
+                self.persist();
+                return Ok(task_id);
+            }
+
+            // Pending tasks count toward the limit, which holds the slot until it runs
+            tasks.insert(task_id.clone(), task_info);
+        }
+        self.persist();
+
+        self.spawn_task(app.clone(), task_id.clone(), options);
+
+        Ok(task_id)
+    }
+
+    /// Runs a task that has already been added to `active_tasks` in a separate tokio task.
+    fn spawn_task(&self, app: AppHandle<R>, task_id: String, options: ClaudeTaskOptions) {
+        let runner = self.clone();
+
+        tokio::spawn(async move {
+            // Snapshot the worktree so the files the task touches can be listed afterwards
+            let working_directory = runner.active_tasks.read().await
+                .get(&task_id)
+                .map(|task| PathBuf::from(&task.working_directory));
+            let snapshot = match working_directory.clone() {
+                Some(working_directory) => {
+                    let log_dir = runner.log_dir(&working_directory);
+                    tokio::task::spawn_blocking(move || WorktreeSnapshot::capture(&working_directory, log_dir).ok())
+                        .await
+                        .ok()
+                        .flatten()
+                }
+                None => None,
+            };
+
+            let result = runner.run_claude_task(&app, &task_id, &options).await;
+
+            let modified_files = match (snapshot, working_directory) {
+                (Some(snapshot), Some(working_directory)) => {
+                    tokio::task::spawn_blocking(move || snapshot.modified_files(&working_directory).ok())
+                        .await
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default()
+                }
+                _ => Vec::new(),
+            };
+
+            let exit_code = match &result {
+                Ok(exit_code) => *exit_code,
+                Err(e) => e.downcast_ref::<TaskFailed>().and_then(|failed| failed.0),
+            };
+
+            // Update task status
+            let completed = {
+                let mut tasks = runner.active_tasks.write().await;
+                tasks.get_mut(&task_id).map(|task| {
+                    task.modified_files = modified_files;
+
+                    // cancel_task and shutdown record the final state of the tasks they stop
+                    if !matches!(task.status.as_str(), "cancelled" | "interrupted") {
+                        match result {
+                            Ok(_) => {
+                                task.status = "completed".to_string();
+                                task.completed_at = Some(Utc::now());
+                                // Markers seen in the output of a successful run were harmless
+                                task.failure_reason = None;
+                            }
+                            Err(e) => {
+                                task.status = if e.downcast_ref::<TaskTimedOut>().is_some() {
+                                    "timed_out".to_string()
+                                } else {
+                                    "failed".to_string()
+                                };
+                                task.completed_at = Some(Utc::now());
+                                task.push_output(format!("Error: {}", e), runner.settings().config.max_output_lines);
+                            }
+                        }
+                        emit_status(&app, &task_id, &task.status);
+                    }
+
+                    // A killed process's exit code says nothing about the task
+                    task.exit_code = match task.status.as_str() {
+                        "completed" | "failed" => exit_code,
+                        _ => None,
+                    };
+
+                    (ClaudeTaskCompletedEvent::new(task), task.chain_id.clone().zip(task.step_index))
+                })
+            };
+            runner.persist();
+
+            let (completed, chain) = completed.unzip();
+            let failed = matches!(&completed, Some(completed) if completed.status == "failed");
+            let status = completed.as_ref().map(|completed| completed.status.clone()).unwrap_or_default();
+            if let Some(completed) = completed {
+                let _ = app.emit("claude-task-completed", completed);
+            }
+
+            if let Some((chain_id, step_index)) = chain.flatten() {
+                runner.advance_chain(&chain_id, step_index, &status).await;
+            }
+
+            // Retry transient failures with exponential backoff
+            let settings = runner.settings();
+            let config = &settings.config;
+            if failed && config.auto_retry_count > 0 {
+                let depth = runner.retry_depth(&task_id).await;
+                if depth < config.auto_retry_count {
+                    let backoff = config.auto_retry_backoff_seconds.saturating_mul(1 << depth.min(16));
+                    let retry_runner = runner.clone();
+                    let retry_app = app.clone();
+                    let retry_of = task_id.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(backoff)).await;
+                        let _ = retry_runner.retry_task(&retry_app, &retry_of).await;
+                    });
+                }
+            }
+
+            // A slot has freed up, start whatever is waiting
+            runner.dispatch_queued().await;
+        });
+    }
+
+    /// Starts queued tasks while there are free slots.
+    async fn dispatch_queued(&self) {
+        loop {
+            if self.paused.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let next = {
+                let mut tasks = self.active_tasks.write().await;
+                if active_count(&tasks) >= self.settings().config.max_concurrent_tasks {
+                    return;
+                }
+
+                let next = match self.queue.lock().await.pop_front() {
+                    Some(next) => next,
+                    None => return,
+                };
+                if let Some(task) = tasks.get_mut(&next.task_id) {
+                    task.status = "pending".to_string();
+                }
+                next
+            };
+
+            emit_status(&next.app, &next.task_id, "pending");
+            self.persist();
+            self.spawn_task(next.app, next.task_id, next.options);
+        }
+    }
+
+    async fn run_claude_task(
+        &self,
+        app: &AppHandle<R>,
+        task_id: &str,
+        options: &ClaudeTaskOptions,
+    ) -> Result<Option<i32>> {
+        // Update task status to running
+        let (working_directory, task_description, timeout_seconds, idle_timeout_seconds) = {
+            let mut tasks = self.active_tasks.write().await;
+            let task = tasks.get_mut(task_id).ok_or_else(|| anyhow!("Task not found"))?;
+            // Cancelled or shut down while pending; don't start the process at all
+            if matches!(task.status.as_str(), "cancelled" | "interrupted") {
+                return Err(anyhow!("Claude task was cancelled"));
+            }
+            task.status = "running".to_string();
+            (
+                task.working_directory.clone(),
+                task.description.clone(),
+                task.timeout_seconds,
+                task.idle_timeout_seconds,
+            )
+        };
+        emit_status(app, task_id, "running");
+
+        let log_file = Arc::new(Mutex::new(self.open_log_file(task_id, &working_directory).await?));
+        self.persist();
+
+        let mut cmd = TokioCommand::new(&self.settings().config.claude_command);
+        if options.load_dotenv {
+            match tokio::fs::read_to_string(Path::new(&working_directory).join(".env")).await {
+                Ok(content) => {
+                    cmd.envs(parse_dotenv(&content));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(anyhow!("Failed to read .env: {}", e)),
+            }
+        }
+        let stream_json = options.output_format == OutputFormat::StreamJson;
+        cmd.args(&options.args);
+        if stream_json && !uses_json_output(&options.args) {
+            // The CLI only streams JSON in print mode when --verbose is set as well
+            cmd.args(["--output-format", "stream-json", "--verbose"]);
+        }
+        cmd.arg(&task_description)
+            .envs(&options.env)
+            .current_dir(&working_directory)
+            .stdin(if options.interactive { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // Register the process so cancel_task can kill it. Stopping a task marks it before
+        // killing its process, so checking the status under the process lock means a
+        // cancel either stops the spawn here or finds the process registered.
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let (mut child, stdout, stderr) = {
+            let mut processes = self.processes.lock().await;
+            let stopped = self.active_tasks.read().await
+                .get(task_id)
+                .is_none_or(|task| matches!(task.status.as_str(), "cancelled" | "interrupted"));
+            if stopped {
+                return Err(anyhow!("Claude task was cancelled"));
+            }
+
+            let mut child = cmd.spawn()?;
+            let stdout = child.stdout.take().ok_or_else(|| anyhow!("Failed to open stdout"))?;
+            let stderr = child.stderr.take().ok_or_else(|| anyhow!("Failed to open stderr"))?;
+            let stdin = child.stdin.take().map(|stdin| Arc::new(Mutex::new(stdin)));
+            processes.insert(task_id.to_string(), ProcessHandle { cancel: cancel_tx, stdin });
+            (child, stdout, stderr)
+        };
+
+        // Both readers bump this on every line, which resets the idle timeout
+        let last_output = Arc::new(std::sync::Mutex::new(tokio::time::Instant::now()));
+        let stdout_handle = self.spawn_output_reader(
+            app,
+            task_id,
+            "stdout",
+            stdout,
+            log_file.clone(),
+            last_output.clone(),
+            stream_json,
+        );
+        let stderr_handle = self.spawn_output_reader(app, task_id, "stderr", stderr, log_file, last_output.clone(), false);
+
+        let total_timeout = async {
+            match timeout_seconds {
+                0 => std::future::pending().await,
+                seconds => tokio::time::sleep(tokio::time::Duration::from_secs(seconds)).await,
+            }
+        };
+        let idle_timeout = async {
+            let idle_seconds = match idle_timeout_seconds {
+                Some(idle_seconds) => idle_seconds,
+                None => return std::future::pending().await,
+            };
+            loop {
+                let last = *last_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let deadline = last + tokio::time::Duration::from_secs(idle_seconds);
+                if tokio::time::Instant::now() >= deadline {
+                    return idle_seconds;
+                }
+                tokio::time::sleep_until(deadline).await;
+            }
+        };
+
+        // Wait for the process to complete, time out or be cancelled
+        let outcome = tokio::select! {
+            status = child.wait() => ProcessOutcome::Exited(status),
+            _ = total_timeout => ProcessOutcome::TimedOut(TaskTimedOut::Total(timeout_seconds)),
+            idle_seconds = idle_timeout => ProcessOutcome::TimedOut(TaskTimedOut::Idle(idle_seconds)),
+            Ok(ack) = cancel_rx => ProcessOutcome::Cancelled(ack),
+        };
+
+        self.processes.lock().await.remove(task_id);
+
+        match outcome {
+            ProcessOutcome::Exited(Ok(status)) => {
+                // Wait for all output to be processed
+                let _ = tokio::join!(stdout_handle, stderr_handle);
+
+                if stream_json || uses_json_output(&options.args) {
+                    let mut tasks = self.active_tasks.write().await;
+                    if let Some(task) = tasks.get_mut(task_id) {
+                        task.record_usage();
+                    }
+                }
+
+                if status.success() {
+                    Ok(status.code())
+                } else {
+                    Err(TaskFailed(status.code()).into())
+                }
+            }
+            ProcessOutcome::Exited(Err(e)) => Err(anyhow!("Failed to wait for Claude process: {}", e)),
+            ProcessOutcome::TimedOut(timed_out) => {
+                // Timeout occurred, kill the process
+                let _ = child.kill().await;
+                Err(timed_out.into())
+            }
+            ProcessOutcome::Cancelled(ack) => {
+                // kill() also waits for the process to exit; the reader tasks
+                // finish on their own once the pipes close
+                let _ = child.kill().await;
+                let _ = ack.send(());
+                Err(anyhow!("Claude task was cancelled"))
+            }
+        }
+    }
+
+    /// The configured log directory, or `<working_directory>/.whiplash/logs`.
+    fn log_dir(&self, working_directory: &Path) -> PathBuf {
+        match &self.settings().config.log_dir {
+            Some(log_dir) => log_dir.clone(),
+            None => working_directory.join(WHIPLASH_DIR).join("logs"),
+        }
+    }
+
+    /// Creates the log file for a task and records its path on the task.
+    async fn open_log_file(&self, task_id: &str, working_directory: &str) -> Result<File> {
+        let log_dir = self.log_dir(Path::new(working_directory));
+        if self.settings().config.log_dir.is_none() {
+            // Logs in the worktree would otherwise leave it dirty after every task.
+            // Working directories outside a git repo have nothing to exclude from.
+            let exclude_from = PathBuf::from(working_directory);
+            let _ = tokio::task::spawn_blocking(move || exclude_whiplash_dir(&exclude_from)).await;
+        }
+        tokio::fs::create_dir_all(&log_dir).await?;
+
+        let log_path = log_dir.join(format!("{}.log", task_id));
+        let log_file = File::create(&log_path).await?;
+
+        let mut tasks = self.active_tasks.write().await;
+        if let Some(task) = tasks.get_mut(task_id) {
+            task.log_path = Some(log_path.to_string_lossy().to_string());
+        }
+
+        Ok(log_file)
+    }
+
+    /// Forwards each line of a process stream into the task's output buffer, recording
+    /// when it arrived in `last_output`.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_output_reader<S>(
+        &self,
+        app: &AppHandle<R>,
+        task_id: &str,
+        stream: &'static str,
+        reader: S,
+        log_file: Arc<Mutex<File>>,
+        last_output: Arc<std::sync::Mutex<tokio::time::Instant>>,
+        parse_events: bool,
+    ) -> JoinHandle<()>
+    where
+        S: AsyncRead + Unpin + Send + 'static,
+    {
+        let active_tasks = self.active_tasks.clone();
+        let settings = self.settings();
+        let max_output_lines = settings.config.max_output_lines;
+        let task_id = task_id.to_string();
+        let app = app.clone();
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            let mut batch = OutputBatch::default();
+
+            loop {
+                // Lines are applied to the shared task map in batches, so chatty tasks
+                // don't contend on its lock for every line
+                let next_line = match batch.first_line_at {
+                    None => lines.next_line().await,
+                    Some(first_line_at) => {
+                        let remaining = OUTPUT_FLUSH_INTERVAL.saturating_sub(first_line_at.elapsed());
+                        match tokio::time::timeout(remaining, lines.next_line()).await {
+                            Ok(next_line) => next_line,
+                            Err(_) => {
+                                batch.flush(&app, &task_id, &active_tasks, &log_file, max_output_lines).await;
+                                continue;
+                            }
+                        }
+                    }
+                };
+
+                let line = match next_line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                };
+                *last_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = tokio::time::Instant::now();
+
+                let progress = parse_progress(&settings.progress_patterns, &line);
+                let failure_reason = settings.failure_patterns.iter()
+                    .find(|(_, pattern)| pattern.is_match(&line))
+                    .map(|(reason, _)| reason.clone());
+                if parse_events {
+                    if let Some(event) = ClaudeEvent::parse(&line) {
+                        batch.events.push(event);
+                    }
+                }
+                batch.push(
+                    match stream {
+                        "stderr" => format!("stderr: {}", line),
+                        _ => line.clone(),
+                    },
+                    progress,
+                    failure_reason,
+                );
+
+                // Events don't touch the map, so the UI still sees every line as it arrives
+                emit_output(&app, &task_id, line, stream);
+
+                if batch.entries.len() >= OUTPUT_FLUSH_LINES {
+                    batch.flush(&app, &task_id, &active_tasks, &log_file, max_output_lines).await;
+                }
+            }
+
+            batch.flush(&app, &task_id, &active_tasks, &log_file, max_output_lines).await;
+        })
+    }
+
+    /// Number of pending or running tasks per worktree name.
+    pub async fn active_task_counts(&self) -> HashMap<String, usize> {
+        let tasks = self.active_tasks.read().await;
+        let mut counts = HashMap::new();
+        for task in tasks.values().filter(|task| task.status == "pending" || task.status == "running") {
+            *counts.entry(task.worktree_name.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub async fn get_task_status(&self, task_id: &str) -> Result<ClaudeTaskInfo> {
+        let tasks = self.active_tasks.read().await;
+        let mut task = tasks.get(task_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Task not found"))?;
+        task.queue_position = self.queue_positions().await.get(task_id).copied();
+        Ok(task)
+    }
+
+    pub async fn get_task_output_since(&self, task_id: &str, from_line: usize) -> Result<ClaudeTaskOutputChunk> {
+        let tasks = self.active_tasks.read().await;
+        let task = tasks.get(task_id).ok_or_else(|| anyhow!("Task not found"))?;
+
+        // Line numbers are absolute, so account for lines dropped from the front of the buffer
+        let total_lines = task.total_lines_seen;
+        let first_retained = total_lines - task.output.len();
+        let lines = task.output
+            .iter()
+            .skip(from_line.saturating_sub(first_retained))
+            .cloned()
+            .collect();
+
+        Ok(ClaudeTaskOutputChunk { lines, total_lines })
+    }
+
+    pub async fn get_task_log_path(&self, task_id: &str) -> Result<String> {
+        let tasks = self.active_tasks.read().await;
+        let task = tasks.get(task_id).ok_or_else(|| anyhow!("Task not found"))?;
+        task.log_path.clone().ok_or_else(|| anyhow!("Task has no log file yet"))
+    }
+
+    pub async fn list_tasks(&self, query: &ClaudeTaskQuery) -> Result<ClaudeTaskPage> {
+        let tasks = self.active_tasks.read().await;
+        let positions = self.queue_positions().await;
+
+        let mut matching: Vec<&ClaudeTaskInfo> = tasks.values()
+            .filter(|task| match &query.status_filter {
+                Some(statuses) => statuses.contains(&task.status),
+                None => true,
+            })
+            .filter(|task| match &query.worktree_name {
+                Some(worktree_name) => &task.worktree_name == worktree_name,
+                None => true,
+            })
+            .collect();
+        // Most recent first, with the id as a tiebreak so the order is stable between
+        // calls and pages don't repeat or skip tasks
+        matching.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| a.id.cmp(&b.id)));
+
+        let total = matching.len();
+        let page = matching.into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .map(|task| {
+                let mut task = task.clone();
+                task.queue_position = positions.get(&task.id).copied();
+                if !query.include_output {
+                    task.output.clear();
+                    task.events.clear();
+                }
+                task
+            })
+            .collect();
+
+        Ok(ClaudeTaskPage {
+            tasks: page,
+            total,
+            paused: self.is_paused(),
+        })
+    }
+
+    /// Stops queued tasks from starting. Running tasks carry on until they finish.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets queued tasks start again, filling any free slots straight away.
+    pub async fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.dispatch_queued().await;
     }
-}
 
-pub struct ClaudeRunner {
-    config: ClaudeRunnerConfig,
-    active_tasks: Arc<RwLock<HashMap<String, ClaudeTaskInfo>>>,
-}
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
 
-impl ClaudeRunner {
-    pub fn new(config: ClaudeRunnerConfig) -> Self {
-        Self {
-            config,
-            active_tasks: Arc::new(RwLock::new(HashMap::new())),
-        }
+    async fn queue_positions(&self) -> HashMap<String, usize> {
+        let queue = self.queue.lock().await;
+        queue.iter()
+            .enumerate()
+            .map(|(position, queued)| (queued.task_id.clone(), position))
+            .collect()
     }
 
-    pub async fn start_task(&self, worktree_name: &str, working_directory: &str, task_description: &str) -> Result<String> {
-        let task_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+    /// Checks that `claude_command` can be found and runs, by resolving it on `PATH` and
+    /// running it with `--version`.
+    pub async fn check_available(&self) -> ClaudeAvailability {
+        let settings = self.settings();
+        let command = &settings.config.claude_command;
+        let path = resolve_command(command);
 
-        let task_info = ClaudeTaskInfo {
-            id: task_id.clone(),
-            description: task_description.to_string(),
-            status: "pending".to_string(),
-            started_at: Some(now),
-            completed_at: None,
-            output: Vec::new(),
-            working_directory: working_directory.to_string(),
-            worktree_name: worktree_name.to_string(),
+        let mut cmd = TokioCommand::new(command);
+        cmd.arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let output = tokio::time::timeout(tokio::time::Duration::from_secs(10), cmd.output()).await;
+        let path = path.map(|path| path.to_string_lossy().to_string());
+        let unavailable = |path: Option<String>, error: String| ClaudeAvailability {
+            available: false,
+            version: None,
+            path,
+            error: Some(error),
         };
 
-        // Check if we've reached the maximum concurrent tasks
-        {
-            let tasks = self.active_tasks.read().await;
-            let active_count = tasks.values().filter(|t| t.status == "running").count();
-            if active_count >= self.config.max_concurrent_tasks {
-                return Err(anyhow!("Maximum concurrent tasks reached"));
+        match output {
+            Ok(Ok(output)) if output.status.success() => {
+                let version = String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .next()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty());
+                ClaudeAvailability { available: true, version, path, error: None }
             }
+            Ok(Ok(output)) => unavailable(path, format!(
+                "'{} --version' exited with code {}: {}",
+                command,
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim(),
+            )),
+            Ok(Err(e)) => unavailable(path, format!("Could not run '{}': {}. Is Claude installed and on PATH?", command, e)),
+            Err(_) => unavailable(path, format!("'{} --version' timed out", command)),
         }
+    }
 
-        // Add task to active tasks
-        {
-            let mut tasks = self.active_tasks.write().await;
-            tasks.insert(task_id.clone(), task_info);
-        }
-
-        // Start the task in a separate tokio task
-        let task_id_clone = task_id.clone();
-        let working_directory = working_directory.to_string();
-        let task_description = task_description.to_string();
-        let claude_command = self.config.claude_command.clone();
-        let timeout_seconds = self.config.timeout_seconds;
-        let active_tasks = self.active_tasks.clone();
+    /// Writes a line to the stdin of a running task started with `interactive`.
+    pub async fn send_input(&self, task_id: &str, text: &str) -> Result<()> {
+        // Clone the handle out so the process map isn't locked while writing
+        let stdin = {
+            let processes = self.processes.lock().await;
+            let handle = processes.get(task_id).ok_or_else(|| anyhow!("Task is not running"))?;
+            handle.stdin.clone().ok_or_else(|| {
+                WhiplashError::InvalidInput("Task wasn't started as interactive".to_string())
+            })?
+        };
 
-        tokio::spawn(async move {
-            let result = Self::run_claude_task(
-                &claude_command,
-                &working_directory,
-                &task_description,
-                timeout_seconds,
-                &task_id_clone,
-                active_tasks.clone(),
-            ).await;
+        let mut stdin = stdin.lock().await;
+        stdin.write_all(format!("{}\n", text).as_bytes()).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
 
-            // Update task status
-            let mut tasks = active_tasks.write().await;
-            if let Some(task) = tasks.get_mut(&task_id_clone) {
-                match result {
-                    Ok(output) => {
-                        task.status = "completed".to_string();
-                        task.completed_at = Some(Utc::now());
-                        task.output = output;
-                    }
-                    Err(e) => {
-                        task.status = "failed".to_string();
-                        task.completed_at = Some(Utc::now());
-                        task.output.push(format!("Error: {}", e));
-                    }
+    /// Cancels a task that hasn't finished. Queued tasks are taken off the queue and
+    /// pending ones never get a process; running ones are killed. Every cancelled task
+    /// gets a `claude-task-completed` event: queued ones here, since nothing else will
+    /// run for them, and the rest once their run winds down.
+    pub async fn cancel_task(&self, app: &AppHandle<R>, task_id: &str) -> Result<()> {
+        let (was_running, was_queued) = {
+            let mut tasks = self.active_tasks.write().await;
+            let task = tasks.get_mut(task_id).ok_or_else(|| anyhow!("Task not found"))?;
+            let was_queued = task.status == "queued";
+            let was_running = match task.status.as_str() {
+                "queued" => {
+                    self.queue.lock().await.retain(|queued| queued.task_id != task_id);
+                    false
                 }
-            }
-        });
-
-        Ok(task_id)
-    }
+                // run_claude_task checks for this before starting the process
+                "pending" => false,
+                "running" => true,
+                status => {
+                    return Err(WhiplashError::Conflict(format!("Task has already finished ({})", status)).into());
+                }
+            };
+            task.status = "cancelled".to_string();
+            task.completed_at = Some(Utc::now());
+            (was_running, was_queued.then(|| ClaudeTaskCompletedEvent::new(task)))
+        };
+        emit_status(app, task_id, "cancelled");
+        if let Some(completed) = was_queued {
+            let _ = app.emit("claude-task-completed", completed);
+        }
+        self.persist();
 
-    async fn run_claude_task(
-        claude_command: &str,
-        working_directory: &str,
-        task_description: &str,
-        timeout_seconds: u64,
-        task_id: &str,
-        active_tasks: Arc<RwLock<HashMap<String, ClaudeTaskInfo>>>,
-    ) -> Result<Vec<String>> {
-        // Update task status to running
-        {
-            let mut tasks = active_tasks.write().await;
-            if let Some(task) = tasks.get_mut(task_id) {
-                task.status = "running".to_string();
-            }
+        // Kill the process without holding the task lock
+        if was_running {
+            self.kill_process(task_id).await;
         }
 
-        let mut cmd = TokioCommand::new(claude_command);
-        cmd.arg(task_description)
-            .current_dir(working_directory)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+        Ok(())
+    }
 
-        let mut child = cmd.spawn()?;
-        let stdout = child.stdout.take().ok_or_else(|| anyhow!("Failed to open stdout"))?;
-        let stderr = child.stderr.take().ok_or_else(|| anyhow!("Failed to open stderr"))?;
-
-        let active_tasks_clone = active_tasks.clone();
-        let task_id_clone = task_id.to_string();
-
-        // Handle stdout
-        let stdout_handle = tokio::spawn(async move {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            
-            while let Ok(Some(line)) = lines.next_line().await {
-                // Update task output in real-time
-                {
-                    let mut tasks = active_tasks_clone.write().await;
-                    if let Some(task) = tasks.get_mut(&task_id_clone) {
-                        task.output.push(line.clone());
-                    }
-                }
-            }
-        });
+    pub async fn cancel_all_tasks(&self, app: &AppHandle<R>) -> Result<usize> {
+        // Mark everything cancelled in one pass, then release the lock before killing
+        // so the output readers can drain while the processes shut down
+        let (cancelled, never_started): (Vec<String>, Vec<ClaudeTaskCompletedEvent>) = {
+            let mut tasks = self.active_tasks.write().await;
+            self.queue.lock().await.clear();
 
-        // Handle stderr
-        let active_tasks_clone2 = active_tasks.clone();
-        let task_id_clone2 = task_id.to_string();
-        let stderr_handle = tokio::spawn(async move {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            
-            while let Ok(Some(line)) = lines.next_line().await {
-                {
-                    let mut tasks = active_tasks_clone2.write().await;
-                    if let Some(task) = tasks.get_mut(&task_id_clone2) {
-                        task.output.push(format!("stderr: {}", line));
+            let now = Utc::now();
+            let mut never_started = Vec::new();
+            let cancelled = tasks.values_mut()
+                .filter(|task| matches!(task.status.as_str(), "queued" | "pending" | "running"))
+                .map(|task| {
+                    let was_queued = task.status == "queued";
+                    task.status = "cancelled".to_string();
+                    task.completed_at = Some(now);
+                    // Queued tasks have no run that would report their completion
+                    if was_queued {
+                        never_started.push(ClaudeTaskCompletedEvent::new(task));
                     }
-                }
-            }
-        });
-
-        // Wait for the process to complete with timeout
-        let result = tokio::time::timeout(
-            tokio::time::Duration::from_secs(timeout_seconds),
-            child.wait()
-        ).await;
+                    task.id.clone()
+                })
+                .collect();
+            (cancelled, never_started)
+        };
+        self.persist();
+        for completed in never_started {
+            let _ = app.emit("claude-task-completed", completed);
+        }
 
-        match result {
-            Ok(Ok(status)) => {
-                // Wait for all output to be processed
-                let _ = tokio::join!(stdout_handle, stderr_handle);
-                
-                if status.success() {
-                    // Get final output from task
-                    let tasks = active_tasks.read().await;
-                    if let Some(task) = tasks.get(task_id) {
-                        Ok(task.output.clone())
-                    } else {
-                        Ok(vec!["Task completed successfully".to_string()])
-                    }
-                } else {
-                    Err(anyhow!("Claude command failed with exit code: {}", status.code().unwrap_or(-1)))
-                }
-            }
-            Ok(Err(e)) => Err(anyhow!("Failed to wait for Claude process: {}", e)),
-            Err(_) => {
-                // Timeout occurred, kill the process
-                let _ = child.kill().await;
-                Err(anyhow!("Claude command timed out after {} seconds", timeout_seconds))
+        let mut stopped = 0;
+        for task_id in &cancelled {
+            emit_status(app, task_id, "cancelled");
+            if self.kill_process(task_id).await {
+                stopped += 1;
             }
         }
-    }
 
-    pub async fn get_task_status(&self, task_id: &str) -> Result<ClaudeTaskInfo> {
-        let tasks = self.active_tasks.read().await;
-        tasks.get(task_id)
-            .cloned()
-            .ok_or_else(|| anyhow!("Task not found"))
+        Ok(stopped)
     }
 
-    pub async fn list_tasks(&self) -> Result<Vec<ClaudeTaskInfo>> {
-        let tasks = self.active_tasks.read().await;
-        Ok(tasks.values().cloned().collect())
+    /// Stops all work before the app exits: queued tasks are dropped and running
+    /// processes are killed, waiting up to `grace` for them to exit. Without this, claude
+    /// processes keep running in the background after the app is closed. Stopped tasks
+    /// are marked `"interrupted"`, matching what a restart would record.
+    pub async fn shutdown(&self, grace: std::time::Duration) {
+        let stopped: Vec<String> = {
+            let mut tasks = self.active_tasks.write().await;
+            self.queue.lock().await.clear();
+
+            let now = Utc::now();
+            tasks.values_mut()
+                .filter(|task| matches!(task.status.as_str(), "queued" | "pending" | "running"))
+                .map(|task| {
+                    task.status = "interrupted".to_string();
+                    task.completed_at = Some(now);
+                    task.id.clone()
+                })
+                .collect()
+        };
+        self.persist();
+
+        let mut kills = tokio::task::JoinSet::new();
+        for task_id in stopped {
+            let runner = self.clone();
+            kills.spawn(async move { runner.kill_process(&task_id).await });
+        }
+        let _ = tokio::time::timeout(grace, async {
+            while kills.join_next().await.is_some() {}
+        }).await;
+
+        // The app exits next, so a scheduled write would never happen
+        self.flush_store().await;
     }
 
-    pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
-        let mut tasks = self.active_tasks.write().await;
-        if let Some(task) = tasks.get_mut(task_id) {
-            if task.status == "running" {
-                task.status = "cancelled".to_string();
-                task.completed_at = Some(Utc::now());
-                // Note: In a real implementation, we'd need to track the process handle
-                // and kill it here
+    /// Kills the process backing a task and waits for it to exit. Returns whether a
+    /// process was actually stopped.
+    async fn kill_process(&self, task_id: &str) -> bool {
+        let handle = self.processes.lock().await.remove(task_id);
+        if let Some(handle) = handle {
+            let (ack_tx, ack_rx) = oneshot::channel();
+            if handle.cancel.send(ack_tx).is_ok() {
+                return ack_rx.await.is_ok();
             }
         }
-        Ok(())
+        false
     }
 
+    /// Drops finished tasks older than their status's retention, then the oldest
+    /// finished tasks beyond `max_tasks`. Returns how many were removed.
     pub async fn cleanup_completed_tasks(&self) -> Result<usize> {
         let mut tasks = self.active_tasks.write().await;
         let initial_count = tasks.len();
+        let now = Utc::now();
+        let config = self.config();
         
         tasks.retain(|_, task| {
-            match task.status.as_str() {
-                "completed" | "failed" | "cancelled" => {
-                    // Keep tasks completed less than 1 hour ago
-                    if let Some(completed_at) = task.completed_at {
-                        let one_hour_ago = Utc::now() - chrono::Duration::hours(1);
-                        completed_at > one_hour_ago
-                    } else {
-                        false
-                    }
+            if !is_finished(&task.status) {
+                return true; // Keep queued, pending and running tasks
+            }
+
+            let retention_seconds = config.retention_seconds
+                .get(&task.status)
+                .copied()
+                .unwrap_or(config.default_retention_seconds);
+            match task.completed_at {
+                Some(completed_at) => {
+                    // A negative age (clock changes) can't be converted and keeps the task
+                    let age_seconds = now.signed_duration_since(completed_at).num_seconds();
+                    u64::try_from(age_seconds).map_or(true, |age_seconds| age_seconds < retention_seconds)
                 }
-                _ => true, // Keep pending and running tasks
+                None => false,
             }
         });
-        
-        Ok(initial_count - tasks.len())
+
+        if let Some(max_tasks) = config.max_tasks {
+            let mut finished: Vec<(Option<DateTime<Utc>>, String)> = tasks.values()
+                .filter(|task| is_finished(&task.status))
+                .map(|task| (task.completed_at.or(task.started_at), task.id.clone()))
+                .collect();
+            if finished.len() > max_tasks {
+                // Oldest first; tasks with no timestamps sort first and go first
+                finished.sort();
+                let excess = finished.len() - max_tasks;
+                for (_, task_id) in finished.into_iter().take(excess) {
+                    tasks.remove(&task_id);
+                }
+            }
+        }
+        let removed = initial_count - tasks.len();
+        drop(tasks);
+
+        self.persist();
+        Ok(removed)
     }
 }
 
-// Global runner instance
-static mut CLAUDE_RUNNER: Option<ClaudeRunner> = None;
-static INIT: std::sync::Once = std::sync::Once::new();
+/// Finds the file a command would run as: the command itself if it's a path, otherwise
+/// the first match on `PATH` (trying the usual executable extensions on Windows).
+fn resolve_command(command: &str) -> Option<PathBuf> {
+    let command_path = Path::new(command);
+    if command_path.components().count() > 1 {
+        return command_path.is_file().then(|| command_path.to_path_buf());
+    }
 
-fn get_claude_runner() -> &'static ClaudeRunner {
-    unsafe {
-        INIT.call_once(|| {
-            CLAUDE_RUNNER = Some(ClaudeRunner::new(ClaudeRunnerConfig::default()));
-        });
-        CLAUDE_RUNNER.as_ref().unwrap()
+    let extensions: &[&str] = if cfg!(windows) { &["exe", "cmd", "bat"] } else { &[] };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| {
+            let candidate = dir.join(command);
+            std::iter::once(candidate.clone())
+                .chain(extensions.iter().map(move |ext| candidate.with_extension(ext)))
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether a task has reached a final status and is eligible for cleanup.
+fn is_finished(status: &str) -> bool {
+    matches!(status, "completed" | "failed" | "cancelled" | "timed_out" | "interrupted" | "skipped")
+}
+
+/// Number of tasks holding a slot toward `max_concurrent_tasks`.
+fn active_count(tasks: &HashMap<String, ClaudeTaskInfo>) -> usize {
+    tasks.values()
+        .filter(|task| task.status == "pending" || task.status == "running")
+        .count()
+}
+
+/// Writes tasks to a temporary file and renames it over the store, so a crash mid-write
+/// never leaves a truncated store behind.
+async fn write_task_store(store_path: &Path, tasks: &[ClaudeTaskInfo]) -> Result<()> {
+    if let Some(parent) = store_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
     }
+    let tmp_path = store_path.with_extension("json.tmp");
+    tokio::fs::write(&tmp_path, serde_json::to_vec(tasks)?).await?;
+    tokio::fs::rename(&tmp_path, store_path).await?;
+    Ok(())
+}
+
+fn emit_output<R: Runtime>(app: &AppHandle<R>, task_id: &str, line: String, stream: &str) {
+    let _ = app.emit("claude-task-output", ClaudeTaskOutputEvent {
+        task_id: task_id.to_string(),
+        line,
+        stream: stream.to_string(),
+    });
+}
+
+fn emit_status<R: Runtime>(app: &AppHandle<R>, task_id: &str, status: &str) {
+    emit_status_with_progress(app, task_id, status, None);
+}
+
+fn emit_status_with_progress<R: Runtime>(app: &AppHandle<R>, task_id: &str, status: &str, progress: Option<f32>) {
+    let _ = app.emit("claude-task-status", ClaudeTaskStatusEvent {
+        task_id: task_id.to_string(),
+        status: status.to_string(),
+        progress,
+    });
 }
 
 #[tauri::command]
 pub async fn start_claude_task(
+    app: AppHandle,
+    runner: State<'_, ClaudeRunner>,
     worktree_name: String,
     working_directory: String,
     task_description: String,
-) -> Result<String, String> {
-    let runner = get_claude_runner();
-    runner.start_task(&worktree_name, &working_directory, &task_description)
+    options: Option<ClaudeTaskOptions>,
+) -> Result<String, WhiplashError> {
+    runner.start_task(&app, &worktree_name, &working_directory, &task_description, options.unwrap_or_default())
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn start_claude_task_chain(
+    app: AppHandle,
+    runner: State<'_, ClaudeRunner>,
+    worktree_name: String,
+    working_directory: String,
+    steps: Vec<TaskStep>,
+) -> Result<String, WhiplashError> {
+    runner.start_task_chain(&app, &worktree_name, &working_directory, steps)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_claude_task_chain(
+    app: AppHandle,
+    runner: State<'_, ClaudeRunner>,
+    chain_id: String,
+) -> Result<usize, WhiplashError> {
+    runner.cancel_task_chain(&app, &chain_id)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn retry_claude_task(app: AppHandle, runner: State<'_, ClaudeRunner>, task_id: String) -> Result<String, WhiplashError> {
+    runner.retry_task(&app, &task_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(WhiplashError::from)
 }
 
 #[tauri::command]
-pub async fn get_claude_task_status(task_id: String) -> Result<ClaudeTaskInfo, String> {
-    let runner = get_claude_runner();
+pub async fn get_claude_task_status(runner: State<'_, ClaudeRunner>, task_id: String) -> Result<ClaudeTaskInfo, WhiplashError> {
     runner.get_task_status(&task_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(WhiplashError::from)
 }
 
 #[tauri::command]
-pub async fn list_claude_tasks() -> Result<Vec<ClaudeTaskInfo>, String> {
-    let runner = get_claude_runner();
-    runner.list_tasks()
+pub async fn get_claude_task_output_since(
+    runner: State<'_, ClaudeRunner>,
+    task_id: String,
+    from_line: usize,
+) -> Result<ClaudeTaskOutputChunk, WhiplashError> {
+    runner.get_task_output_since(&task_id, from_line)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(WhiplashError::from)
 }
 
 #[tauri::command]
-pub async fn cancel_claude_task(task_id: String) -> Result<(), String> {
-    let runner = get_claude_runner();
-    runner.cancel_task(&task_id)
+pub async fn get_claude_task_log_path(runner: State<'_, ClaudeRunner>, task_id: String) -> Result<String, WhiplashError> {
+    runner.get_task_log_path(&task_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(WhiplashError::from)
 }
 
 #[tauri::command]
-pub async fn cleanup_completed_claude_tasks() -> Result<usize, String> {
-    let runner = get_claude_runner();
+pub async fn list_claude_tasks(
+    runner: State<'_, ClaudeRunner>,
+    status_filter: Option<Vec<String>>,
+    worktree_name: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    include_output: Option<bool>,
+) -> Result<ClaudeTaskPage, WhiplashError> {
+    let query = ClaudeTaskQuery {
+        status_filter,
+        worktree_name,
+        limit,
+        offset: offset.unwrap_or(0),
+        include_output: include_output.unwrap_or(false),
+    };
+    runner.list_tasks(&query)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn get_runner_config(runner: State<'_, ClaudeRunner>) -> Result<ClaudeRunnerConfig, WhiplashError> {
+    Ok(runner.config())
+}
+
+#[tauri::command]
+pub async fn update_runner_config(
+    runner: State<'_, ClaudeRunner>,
+    config: ClaudeRunnerConfig,
+) -> Result<(), WhiplashError> {
+    runner.update_config(config).await.map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn pause_scheduler(runner: State<'_, ClaudeRunner>) -> Result<(), WhiplashError> {
+    runner.pause();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_scheduler(runner: State<'_, ClaudeRunner>) -> Result<(), WhiplashError> {
+    runner.resume().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn send_claude_task_input(runner: State<'_, ClaudeRunner>, task_id: String, text: String) -> Result<(), WhiplashError> {
+    runner.send_input(&task_id, &text)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_claude_task(app: AppHandle, runner: State<'_, ClaudeRunner>, task_id: String) -> Result<(), WhiplashError> {
+    runner.cancel_task(&app, &task_id)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_all_claude_tasks(app: AppHandle, runner: State<'_, ClaudeRunner>) -> Result<usize, WhiplashError> {
+    runner.cancel_all_tasks(&app)
+        .await
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn cleanup_completed_claude_tasks(runner: State<'_, ClaudeRunner>) -> Result<usize, WhiplashError> {
     runner.cleanup_completed_tasks()
         .await
-        .map_err(|e| e.to_string())
-}
\ No newline at end of file
+        .map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn check_claude_available(runner: State<'_, ClaudeRunner>) -> Result<ClaudeAvailability, WhiplashError> {
+    Ok(runner.check_available().await)
+}
+
+// The tasks run shell scripts in place of the claude CLI
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tauri::test::{mock_app, MockRuntime};
+
+    fn process_alive(pid: &str) -> bool {
+        std::process::Command::new("kill")
+            .args(["-0", pid])
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// A runner whose "claude" is `sh`, so each task's args are `-c <script>`.
+    fn shell_runner(max_concurrent_tasks: usize) -> ClaudeRunner<MockRuntime> {
+        ClaudeRunner::new(ClaudeRunnerConfig {
+            claude_command: "sh".to_string(),
+            max_concurrent_tasks,
+            ..ClaudeRunnerConfig::default()
+        })
+    }
+
+    fn shell_task(script: &str) -> ClaudeTaskOptions {
+        ClaudeTaskOptions {
+            args: vec!["-c".to_string(), script.to_string()],
+            ..ClaudeTaskOptions::default()
+        }
+    }
+
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(10);
+        while !condition() {
+            assert!(tokio::time::Instant::now() < deadline, "timed out waiting");
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    async fn wait_for_finish(runner: &ClaudeRunner<MockRuntime>, task_id: &str) -> ClaudeTaskInfo {
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(30);
+        loop {
+            let task = runner.get_task_status(task_id).await.unwrap();
+            if is_finished(&task.status) {
+                return task;
+            }
+            assert!(tokio::time::Instant::now() < deadline, "task {} still {}", task_id, task.status);
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_running_task_kills_its_process() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let runner = shell_runner(1);
+
+        let task_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "sleeper", shell_task("echo $$ > pid; exec sleep 30"))
+            .await
+            .unwrap();
+        wait_until(|| std::fs::read_to_string(&pid_file).is_ok_and(|pid| pid.ends_with('\n'))).await;
+        let pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        assert!(process_alive(&pid));
+
+        runner.cancel_task(app.handle(), &task_id).await.unwrap();
+
+        // cancel_task waits for the kill, so the process is already reaped
+        assert!(!process_alive(&pid), "process {} is still alive", pid);
+        assert_eq!(wait_for_finish(&runner, &task_id).await.status, "cancelled");
+    }
+
+    /// Starts a task that stays between being marked running and spawning its process
+    /// until the returned FIFO .env is written.
+    async fn start_blocked_on_env(
+        runner: &ClaudeRunner<MockRuntime>,
+        app: &AppHandle<MockRuntime>,
+        dir: &Path,
+    ) -> (String, PathBuf) {
+        let env_path = dir.join(".env");
+        assert!(std::process::Command::new("mkfifo").arg(&env_path).status().unwrap().success());
+        let options = ClaudeTaskOptions {
+            load_dotenv: true,
+            ..shell_task("touch started; exec sleep 30")
+        };
+        let task_id = runner
+            .start_task(app, "main", &dir.to_string_lossy(), "blocked", options)
+            .await
+            .unwrap();
+
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(10);
+        while runner.get_task_status(&task_id).await.unwrap().status != "running" {
+            assert!(tokio::time::Instant::now() < deadline, "task never started running");
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+        (task_id, env_path)
+    }
+
+    async fn unblock_env(env_path: PathBuf) {
+        tokio::task::spawn_blocking(move || std::fs::write(env_path, "")).await.unwrap().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn cancelling_before_the_process_is_registered_never_starts_it() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(1);
+        let (task_id, env_path) = start_blocked_on_env(&runner, app.handle(), dir.path()).await;
+
+        runner.cancel_task(app.handle(), &task_id).await.unwrap();
+        unblock_env(env_path).await;
+
+        assert_eq!(runner.get_task_status(&task_id).await.unwrap().status, "cancelled");
+        assert!(runner.processes.lock().await.is_empty());
+        assert!(!dir.path().join("started").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutting_down_before_the_process_is_registered_never_starts_it() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(1);
+        let (task_id, env_path) = start_blocked_on_env(&runner, app.handle(), dir.path()).await;
+
+        runner.shutdown(std::time::Duration::from_secs(5)).await;
+        unblock_env(env_path).await;
+
+        assert_eq!(runner.get_task_status(&task_id).await.unwrap().status, "interrupted");
+        assert!(runner.processes.lock().await.is_empty());
+        assert!(!dir.path().join("started").exists());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_pending_task_never_starts_it() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(1);
+
+        // On the single-threaded test runtime the spawned task can't run until we yield
+        let task_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "pending", shell_task("touch started"))
+            .await
+            .unwrap();
+        assert_eq!(runner.get_task_status(&task_id).await.unwrap().status, "pending");
+
+        runner.cancel_task(app.handle(), &task_id).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+        assert_eq!(runner.get_task_status(&task_id).await.unwrap().status, "cancelled");
+        assert!(runner.processes.lock().await.is_empty());
+        assert!(!dir.path().join("started").exists());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_queued_task_removes_it_from_the_queue() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let working_directory = dir.path().to_string_lossy().to_string();
+        let runner = shell_runner(1);
+
+        let running_id = runner
+            .start_task(app.handle(), "main", &working_directory, "running", shell_task("exec sleep 30"))
+            .await
+            .unwrap();
+        let queued_id = runner
+            .start_task(app.handle(), "main", &working_directory, "queued", shell_task("touch started"))
+            .await
+            .unwrap();
+        assert_eq!(runner.get_task_status(&queued_id).await.unwrap().status, "queued");
+
+        runner.cancel_task(app.handle(), &queued_id).await.unwrap();
+        assert!(runner.queue.lock().await.is_empty());
+
+        // Freeing the slot must not start the cancelled task
+        runner.cancel_task(app.handle(), &running_id).await.unwrap();
+        wait_for_finish(&runner, &running_id).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+        assert_eq!(runner.get_task_status(&queued_id).await.unwrap().status, "cancelled");
+        assert!(!dir.path().join("started").exists());
+    }
+
+    #[tokio::test]
+    async fn default_task_logs_leave_the_worktree_clean() {
+        let app = mock_app();
+        let (dir, repo) = crate::test_support::fixture_repo(&[("README.md", "hello\n")]);
+        let runner = shell_runner(1);
+
+        let task_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "quiet", shell_task("echo done"))
+            .await
+            .unwrap();
+        let task = wait_for_finish(&runner, &task_id).await;
+        assert!(Path::new(&task.log_path.unwrap()).starts_with(dir.path().join(WHIPLASH_DIR)));
+
+        let mut status_options = git2::StatusOptions::new();
+        status_options.include_untracked(true).recurse_untracked_dirs(true);
+        assert!(repo.statuses(Some(&mut status_options)).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tasks_that_write_nothing_report_no_modified_files() {
+        let app = mock_app();
+        let (dir, _repo) = crate::test_support::fixture_repo(&[("README.md", "hello\n")]);
+        let working_directory = dir.path().to_string_lossy().to_string();
+
+        // A log dir inside the worktree that git doesn't ignore, run twice so the second
+        // task also sees the first one's log
+        let runner: ClaudeRunner<MockRuntime> = ClaudeRunner::new(ClaudeRunnerConfig {
+            claude_command: "sh".to_string(),
+            log_dir: Some(dir.path().join("task-logs")),
+            ..ClaudeRunnerConfig::default()
+        });
+        for _ in 0..2 {
+            let task_id = runner
+                .start_task(app.handle(), "main", &working_directory, "quiet", shell_task("echo done"))
+                .await
+                .unwrap();
+            assert_eq!(wait_for_finish(&runner, &task_id).await.modified_files, Vec::<String>::new());
+        }
+
+        let task_id = runner
+            .start_task(app.handle(), "main", &working_directory, "writer", shell_task("echo changed > README.md"))
+            .await
+            .unwrap();
+        assert_eq!(wait_for_finish(&runner, &task_id).await.modified_files, ["README.md"]);
+    }
+
+    #[tokio::test]
+    async fn only_interactive_tasks_wait_for_input() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let working_directory = dir.path().to_string_lossy().to_string();
+        let runner = shell_runner(2);
+
+        // Without a pipe, reading stdin hits EOF straight away
+        let batch_id = runner
+            .start_task(app.handle(), "main", &working_directory, "batch", shell_task("cat; echo done"))
+            .await
+            .unwrap();
+        let batch = wait_for_finish(&runner, &batch_id).await;
+        assert_eq!((batch.status.as_str(), batch.output.back().map(String::as_str)), ("completed", Some("done")));
+
+        let options = ClaudeTaskOptions {
+            interactive: true,
+            ..shell_task("read answer; echo \"got $answer\"")
+        };
+        let prompt_id = runner.start_task(app.handle(), "main", &working_directory, "prompt", options).await.unwrap();
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(10);
+        while runner.send_input(&prompt_id, "yes").await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "task never accepted input");
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+        let prompt = wait_for_finish(&runner, &prompt_id).await;
+        assert_eq!(prompt.output.back().map(String::as_str), Some("got yes"));
+    }
+
+    #[tokio::test]
+    async fn task_store_is_written_after_a_delay_and_on_shutdown() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let store_path = dir.path().join("claude_tasks.json");
+        let stored_status = |task_id: &str| -> Option<String> {
+            let contents = std::fs::read_to_string(&store_path).ok()?;
+            serde_json::from_str::<Vec<ClaudeTaskInfo>>(&contents).ok()?
+                .into_iter()
+                .find(|task| task.id == task_id)
+                .map(|task| task.status)
+        };
+
+        let runner = shell_runner(1).with_store(store_path.clone());
+        let task_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "quick", shell_task("true"))
+            .await
+            .unwrap();
+        wait_for_finish(&runner, &task_id).await;
+        wait_until(|| stored_status(&task_id).as_deref() == Some("completed")).await;
+
+        let running_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "slow", shell_task("sleep 30"))
+            .await
+            .unwrap();
+        wait_until(|| runner.active_tasks.try_read().is_ok_and(|tasks| tasks[&running_id].status == "running")).await;
+        runner.shutdown(std::time::Duration::from_secs(5)).await;
+        assert_eq!(stored_status(&running_id).as_deref(), Some("interrupted"));
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_finished_task_is_a_conflict() {
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(1);
+
+        let task_id = runner
+            .start_task(app.handle(), "main", &dir.path().to_string_lossy(), "quick", shell_task("true"))
+            .await
+            .unwrap();
+        assert_eq!(wait_for_finish(&runner, &task_id).await.status, "completed");
+
+        let error = runner.cancel_task(app.handle(), &task_id).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<WhiplashError>(), Some(WhiplashError::Conflict(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn simultaneous_starts_never_exceed_the_limit() {
+        const LIMIT: usize = 2;
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(LIMIT);
+
+        let barrier = Arc::new(tokio::sync::Barrier::new(LIMIT + 2));
+        let starts: Vec<_> = (0..LIMIT + 2)
+            .map(|index| {
+                let (runner, app, barrier) = (runner.clone(), app.handle().clone(), barrier.clone());
+                let working_directory = dir.path().to_string_lossy().to_string();
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    runner.start_task(&app, "main", &working_directory, &format!("task {}", index), shell_task("exec sleep 30"))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut task_ids = Vec::new();
+        for start in starts {
+            task_ids.push(start.await.unwrap());
+        }
+
+        let mut statuses = Vec::new();
+        for task_id in &task_ids {
+            statuses.push(runner.get_task_status(task_id).await.unwrap().status);
+        }
+        let active = statuses.iter().filter(|status| matches!(status.as_str(), "pending" | "running")).count();
+        let queued = statuses.iter().filter(|status| *status == "queued").count();
+        assert_eq!((active, queued), (LIMIT, 2), "statuses: {:?}", statuses);
+
+        runner.cancel_all_tasks(app.handle()).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_high_output_tasks_keep_every_line() {
+        const LINES: usize = 50_000;
+        let app = mock_app();
+        let dir = tempfile::tempdir().unwrap();
+        let runner = shell_runner(3);
+
+        let mut task_ids = Vec::new();
+        for index in 0..3 {
+            let script = format!("i=1; while [ $i -le {} ]; do echo \"task {} line $i\"; i=$((i + 1)); done", LINES, index);
+            task_ids.push(
+                runner.start_task(app.handle(), "main", &dir.path().to_string_lossy(), "chatty", shell_task(&script))
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // Readers stay responsive while the output is applied in batches
+        let mut slowest_read = std::time::Duration::ZERO;
+        while !runner.active_task_counts().await.is_empty() {
+            let started = std::time::Instant::now();
+            for task_id in &task_ids {
+                runner.get_task_status(task_id).await.unwrap();
+            }
+            slowest_read = slowest_read.max(started.elapsed());
+            tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+        }
+        assert!(slowest_read < std::time::Duration::from_secs(1), "a status read took {:?}", slowest_read);
+
+        for (index, task_id) in task_ids.iter().enumerate() {
+            let task = wait_for_finish(&runner, task_id).await;
+            assert_eq!(task.status, "completed");
+            assert_eq!(task.total_lines_seen, LINES);
+            assert_eq!(task.output.back().unwrap(), &format!("task {} line {}", index, LINES));
+
+            let log = std::fs::read_to_string(task.log_path.unwrap()).unwrap();
+            assert_eq!(log.lines().count(), LINES);
+        }
+    }
+
+    #[tokio::test]
+    async fn config_rejects_zero_concurrency_and_never_saves_env_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("runner_config.json");
+        std::fs::write(&config_path, r#"{"max_concurrent_tasks": 0, "timeout_seconds": 60}"#).unwrap();
+
+        let loaded = ClaudeRunnerConfig::load(&config_path);
+        assert_eq!(loaded.max_concurrent_tasks, ClaudeRunnerConfig::default().max_concurrent_tasks);
+        assert_eq!(loaded.timeout_seconds, 60);
+
+        // No other test reads this variable
+        std::env::set_var("WHIPLASH_CLAUDE_COMMAND", "claude-from-env");
+        let runner: ClaudeRunner<MockRuntime> = ClaudeRunner::new(ClaudeRunnerConfig::load(&config_path))
+            .with_config_path(config_path.clone());
+        let mut config = runner.config();
+        assert_eq!(config.claude_command, "claude-from-env");
+        config.max_concurrent_tasks = 5;
+        runner.update_config(config).await.unwrap();
+        std::env::remove_var("WHIPLASH_CLAUDE_COMMAND");
+
+        assert_eq!(runner.config().claude_command, "claude-from-env");
+        let saved = ClaudeRunnerConfig::load(&config_path);
+        assert_eq!(saved.claude_command, ClaudeRunnerConfig::default().claude_command);
+        assert_eq!(saved.max_concurrent_tasks, 5);
+
+        let invalid = ClaudeRunnerConfig { max_concurrent_tasks: 0, ..runner.config() };
+        assert!(runner.update_config(invalid).await.is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Error returned by every Tauri command. Serializes as `{ code, message }` so the
+/// frontend can branch on `code` instead of parsing messages.
+#[derive(Debug)]
+pub enum WhiplashError {
+    NotFound(String),
+    AlreadyExists(String),
+    Conflict(String),
+    InvalidInput(String),
+    Timeout(String),
+    Git(String),
+    Io(String),
+    Other(String),
+}
+
+impl WhiplashError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WhiplashError::NotFound(_) => "not_found",
+            WhiplashError::AlreadyExists(_) => "already_exists",
+            WhiplashError::Conflict(_) => "conflict",
+            WhiplashError::InvalidInput(_) => "invalid_input",
+            WhiplashError::Timeout(_) => "timeout",
+            WhiplashError::Git(_) => "git",
+            WhiplashError::Io(_) => "io",
+            WhiplashError::Other(_) => "other",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            WhiplashError::NotFound(message)
+            | WhiplashError::AlreadyExists(message)
+            | WhiplashError::Conflict(message)
+            | WhiplashError::InvalidInput(message)
+            | WhiplashError::Timeout(message)
+            | WhiplashError::Git(message)
+            | WhiplashError::Io(message)
+            | WhiplashError::Other(message) => message,
+        }
+    }
+
+    /// The same kind of error with a different message.
+    fn with_message(&self, message: String) -> Self {
+        match self {
+            WhiplashError::NotFound(_) => WhiplashError::NotFound(message),
+            WhiplashError::AlreadyExists(_) => WhiplashError::AlreadyExists(message),
+            WhiplashError::Conflict(_) => WhiplashError::Conflict(message),
+            WhiplashError::InvalidInput(_) => WhiplashError::InvalidInput(message),
+            WhiplashError::Timeout(_) => WhiplashError::Timeout(message),
+            WhiplashError::Git(_) => WhiplashError::Git(message),
+            WhiplashError::Io(_) => WhiplashError::Io(message),
+            WhiplashError::Other(_) => WhiplashError::Other(message),
+        }
+    }
+
+    fn from_git_code(code: git2::ErrorCode, message: String) -> Self {
+        match code {
+            git2::ErrorCode::NotFound => WhiplashError::NotFound(message),
+            git2::ErrorCode::Exists => WhiplashError::AlreadyExists(message),
+            git2::ErrorCode::Conflict | git2::ErrorCode::MergeConflict => WhiplashError::Conflict(message),
+            _ => WhiplashError::Git(message),
+        }
+    }
+}
+
+impl fmt::Display for WhiplashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for WhiplashError {}
+
+impl Serialize for WhiplashError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("WhiplashError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+impl From<git2::Error> for WhiplashError {
+    fn from(error: git2::Error) -> Self {
+        Self::from_git_code(error.code(), error.message().to_string())
+    }
+}
+
+impl From<std::io::Error> for WhiplashError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => WhiplashError::NotFound(error.to_string()),
+            std::io::ErrorKind::AlreadyExists => WhiplashError::AlreadyExists(error.to_string()),
+            _ => WhiplashError::Io(error.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for WhiplashError {
+    fn from(error: anyhow::Error) -> Self {
+        // `{:#}` keeps any context added along the way, e.g. "Failed to create branch 'x': ..."
+        let message = format!("{:#}", error);
+
+        // Errors raised as WhiplashError already carry the right code
+        if let Some(whiplash_error) = error.downcast_ref::<WhiplashError>() {
+            return whiplash_error.with_message(message);
+        }
+
+        if let Some(git_error) = error.downcast_ref::<git2::Error>() {
+            Self::from_git_code(git_error.code(), message)
+        } else if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            match io_error.kind() {
+                std::io::ErrorKind::NotFound => WhiplashError::NotFound(message),
+                std::io::ErrorKind::AlreadyExists => WhiplashError::AlreadyExists(message),
+                _ => WhiplashError::Io(message),
+            }
+        } else {
+            WhiplashError::Other(message)
+        }
+    }
+}
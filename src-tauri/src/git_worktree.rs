@@ -1,204 +1,2818 @@
-use git2::{Repository, Worktree};
+use git2::{
+    BranchType, Commit, Cred, CredentialType, Delta, DiffOptions, FetchOptions, Index, IndexAddOption, Patch, Rebase, RemoteCallbacks,
+    Repository, RepositoryOpenFlags, RepositoryState, ResetType, Signature, Sort, StashFlags, StatusOptions, Worktree, WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions,
+};
+use git2::build::CheckoutBuilder;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use anyhow::{Result, anyhow};
+use crate::claude_runner::ClaudeRunner;
+use crate::error::WhiplashError;
+use anyhow::{Context, Result, anyhow};
+use tauri::{AppHandle, Emitter, State};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitWorktreeInfo {
     pub id: String,
     pub name: String,
+    /// Checked-out branch, or `"(detached)"` when HEAD isn't on a branch.
     pub branch: String,
+    /// Abbreviated sha of the commit HEAD points at.
+    pub head_sha: Option<String>,
     pub path: String,
     pub status: String,
     pub created_at: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
+    /// Commits on the worktree branch that aren't on the base branch.
+    pub ahead: usize,
+    /// Commits on the base branch that aren't on the worktree branch.
+    pub behind: usize,
+    /// False when the worktree branch shares no history with the base branch.
+    pub has_merge_base: bool,
+    /// Upstream tracking branch, e.g. `origin/feature`.
+    pub upstream: Option<String>,
+    /// Whether the branch points at the same commit as its upstream, if it has one.
+    pub up_to_date_with_upstream: Option<bool>,
+    /// Only set by dry-run creates: whether the branch already existed.
+    pub branch_exists: Option<bool>,
+    /// Bytes used by the worktree's files, when requested from `list_worktrees`.
+    pub disk_usage_bytes: Option<u64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WorktreeMetadata {
+    id: String,
+    created_at: DateTime<Utc>,
+    /// Directory the worktree was created in, when it isn't `repo_path/worktrees`.
+    #[serde(default)]
+    base_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeDeletion {
+    pub name: String,
+    pub branch: Option<String>,
+    pub branch_deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeArchive {
+    pub archive_path: String,
+    pub size_bytes: u64,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Fast-forward when possible, otherwise create a merge commit.
+    #[default]
+    Auto,
+    FastForwardOnly,
+    NoFastForward,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub status: String, // "up_to_date", "fast_forward", "merged", "conflicts"
+    pub commit: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseResult {
+    pub status: String, // "completed", "conflicts"
+    /// New HEAD sha once the rebase has completed.
+    pub head_sha: Option<String>,
+    /// 1-based step the rebase stopped at when there are conflicts.
+    pub current_step: Option<usize>,
+    pub total_steps: usize,
+    /// The commit being applied when the rebase stopped.
+    pub conflicting_commit: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchInfo {
+    /// Branch name; remote branches include the remote, e.g. `origin/feature`.
+    pub name: String,
+    pub is_remote: bool,
+    /// Checked out in the main repository.
+    pub is_head: bool,
+    pub upstream: Option<String>,
+    /// Checked out in a worktree, so another worktree can't use it.
+    pub has_worktree: bool,
+    /// Commits ahead of and behind the default branch.
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// What `abort_git_operation` found and undid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbortedOperation {
+    /// `"merge"`, `"rebase"`, `"cherry_pick"`, `"revert"`, `"bisect"`, `"apply_mailbox"`,
+    /// or `"none"` if nothing was in progress.
+    pub operation: String,
+    /// HEAD after cleaning up.
+    pub head_sha: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashInfo {
+    /// Position in the repo-wide stash list; 0 is the most recent.
+    pub index: usize,
+    pub id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub short_sha: String,
+    pub summary: String,
+    pub author: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Everything the dashboard shows for a worktree, gathered in one call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeSummary {
+    pub name: String,
+    pub branch: String,
+    pub head_sha: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Number of files with uncommitted changes, untracked files included.
+    pub dirty_files: usize,
+    pub last_commit: Option<CommitInfo>,
+    /// Pending or running Claude tasks in this worktree.
+    pub running_tasks: usize,
+}
+
+/// Where a worktree's changes are: in the index, in the working tree, or committed on its
+/// branch but not yet in the base branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeChangeBreakdown {
+    pub name: String,
+    /// The branch `committed` and `ahead`/`behind` are measured against, if one was found.
+    pub base_branch: Option<String>,
+    pub staged: Vec<String>,
+    /// Working tree changes not yet staged, untracked files included.
+    pub unstaged: Vec<String>,
+    /// Files changed by commits since the branch diverged from `base_branch`.
+    pub committed: Vec<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeFileDiff {
+    pub file_path: String,
+    pub exists_in_a: bool,
+    pub exists_in_b: bool,
+    /// Unified diff from worktree A's version of the file to worktree B's.
+    pub diff: String,
+}
+
+/// What `get_file_diff` compares the working tree file against.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffBase {
+    /// Unstaged changes only.
+    #[default]
+    Index,
+    /// Staged and unstaged changes together.
+    Head,
+}
+
+/// A single file's uncommitted changes, split into hunks for rendering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub file_path: String,
+    pub base: DiffBase,
+    /// Untracked or newly added, so every line shows as added.
+    pub is_new: bool,
+    pub is_deleted: bool,
+    /// Binary files have no hunks.
+    pub is_binary: bool,
+    pub hunks: Vec<DiffHunk>,
+    /// The same diff as unified diff text.
+    pub patch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@` line, including any function context git adds.
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub change_type: String, // "context", "added", "removed"
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+/// Payload of the `worktree-create-progress` event, sent while a new worktree's files
+/// are checked out.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeCreateProgress {
+    pub name: String,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// A changed file and every state it's in, e.g. `["staged modified", "modified"]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStatus {
+    pub path: String,
+    pub states: Vec<String>,
+}
+
+/// Kinds of change `get_modified_files` reports, as a bit set. Serialized as the raw
+/// bits so the frontend can combine them with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ChangeCategories(pub u32);
+
+impl ChangeCategories {
+    /// Content or type changes, staged or not.
+    pub const MODIFIED: Self = Self(1);
+    /// New files added to the index.
+    pub const ADDED: Self = Self(1 << 1);
+    /// New files git doesn't track yet.
+    pub const UNTRACKED: Self = Self(1 << 2);
+    /// Deletions, staged or not.
+    pub const DELETED: Self = Self(1 << 3);
+    /// Renames, staged or not. Both the old and the new path are reported.
+    pub const RENAMED: Self = Self(1 << 4);
+    pub const ALL: Self = Self(0b11111);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl Default for ChangeCategories {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::ops::BitOr for ChangeCategories {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// How long a disk usage measurement is reused before the worktree is walked again.
+const DISK_USAGE_TTL: Duration = Duration::from_secs(60);
+
+/// Disk usage by worktree path, shared across managers since one is created per command.
+fn disk_usage_cache() -> &'static Mutex<HashMap<PathBuf, (Instant, u64)>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Instant, u64)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Directory whiplash keeps its own files in inside a worktree, such as task logs.
+pub(crate) const WHIPLASH_DIR: &str = ".whiplash";
+
+/// Adds `.whiplash/` to the repo's `info/exclude`, which all of its worktrees share, so
+/// whiplash's own files never show up as untracked changes or make a worktree dirty.
+pub(crate) fn exclude_whiplash_dir(worktree_path: &Path) -> Result<()> {
+    let repo = Repository::open(worktree_path)?;
+    let exclude_path = repo.commondir().join("info").join("exclude");
+    let entry = format!("{}/", WHIPLASH_DIR);
+
+    let mut contents = std::fs::read_to_string(&exclude_path).unwrap_or_default();
+    if contents.lines().any(|line| line.trim() == entry) {
+        return Ok(());
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&entry);
+    contents.push('\n');
+
+    if let Some(parent) = exclude_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&exclude_path, contents)
+        .with_context(|| format!("Failed to update '{}'", exclude_path.display()))
+}
+
+/// Serializes operations that rewrite a repo's refs or main checkout (merge, rebase,
+/// branch deletion, rename, prune), so rapid UI clicks can't interleave them.
+///
+/// There is one lock per repo path: operations on different repos never wait on each
+/// other. Waiters on the same repo are served in the order they asked (tokio's `Mutex`
+/// is fair), so destructive operations run in the order they were requested. Read-only
+/// commands (list, status, diffs) don't take the lock and may observe a repo between
+/// the steps of a locked operation.
+#[derive(Default)]
+pub struct RepoLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl RepoLocks {
+    /// Waits for exclusive access to `repo_path`; held until the guard is dropped.
+    pub async fn lock(&self, repo_path: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        // The same repo can be reached through different spellings of its path
+        let key = std::fs::canonicalize(repo_path).unwrap_or_else(|_| PathBuf::from(repo_path));
+        let lock = self.locks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(key)
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
+
+/// Limits for git operations that can hang on a huge repo or a stalled remote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitTimeoutConfig {
+    /// How long a command may spend in git before it gives up with a `timeout` error.
+    pub operation_timeout_seconds: u64,
+    /// A fetch that receives no data for this long is aborted.
+    pub fetch_stall_timeout_seconds: u64,
+}
+
+impl Default for GitTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            operation_timeout_seconds: 120,
+            fetch_stall_timeout_seconds: 30,
+        }
+    }
+}
+
+impl GitTimeoutConfig {
+    /// Makes libgit2 give up on a remote that sends nothing for `fetch_stall_timeout_seconds`,
+    /// including while connecting. `fetch`'s progress callback only runs when data arrives,
+    /// so it can't catch a remote that stops responding entirely. libgit2's options are
+    /// process-wide globals, so this must run before any other thread uses git2.
+    pub fn apply_remote_timeouts(&self) -> Result<()> {
+        let millis = i32::try_from(self.fetch_stall_timeout_seconds.saturating_mul(1000)).unwrap_or(i32::MAX);
+        // SAFETY: called once at startup, before any thread that could read these options exists
+        unsafe {
+            git2::opts::set_server_connect_timeout_in_milliseconds(millis)?;
+            git2::opts::set_server_timeout_in_milliseconds(millis)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs blocking git work off the async runtime, giving up after `timeout`. git2 calls
+/// can't be interrupted, so on timeout the work keeps running in the background; the
+/// command just stops waiting for it.
+pub(crate) async fn run_blocking<T: Send + 'static>(
+    timeout: Duration,
+    work: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T, WhiplashError> {
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(work)).await {
+        Ok(Ok(result)) => result.map_err(WhiplashError::from),
+        Ok(Err(join_error)) => Err(WhiplashError::Other(format!("Git operation panicked: {}", join_error))),
+        Err(_) => Err(WhiplashError::Timeout(format!(
+            "Git operation did not finish within {} seconds",
+            timeout.as_secs()
+        ))),
+    }
+}
+
+/// Name and email to commit as.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSignature {
+    pub name: String,
+    pub email: String,
+}
+
+/// Used when a repository has no `user.name`/`user.email` configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignatureConfig {
+    pub default_signature: Option<CommitSignature>,
+}
+
+/// Reported as the branch of a worktree whose HEAD is detached, e.g. one created from a tag.
+pub const DETACHED_BRANCH: &str = "(detached)";
+
 pub struct GitWorktreeManager {
     repo_path: PathBuf,
+    /// Bare repos have no checkout of their own; every checkout is a linked worktree.
+    is_bare: bool,
+    /// Fallback for commits made in a repo without `user.name`/`user.email`.
+    default_signature: Option<CommitSignature>,
 }
 
 impl GitWorktreeManager {
     pub fn new(repo_path: impl AsRef<Path>) -> Result<Self> {
+        let repo_path = repo_path.as_ref().to_path_buf();
+        let is_bare = Self::open_repo_at(&repo_path)?.is_bare();
         Ok(Self {
-            repo_path: repo_path.as_ref().to_path_buf(),
+            repo_path,
+            is_bare,
+            default_signature: None,
         })
     }
 
-    pub fn create_worktree(&self, name: &str, branch: &str) -> Result<GitWorktreeInfo> {
-        let repo = Repository::open(&self.repo_path)?;
-        let worktree_path = self.repo_path.join("worktrees").join(name);
-        
-        // Create worktree directory if it doesn't exist
-        std::fs::create_dir_all(&worktree_path)?;
+    /// Opens the main repository. `repo_path` may be a working tree (whose `.git` can be a
+    /// file pointing at a separate git dir), a git dir, or a bare repository.
+    pub fn open_repo(&self) -> Result<Repository> {
+        Self::open_repo_at(&self.repo_path)
+    }
+
+    fn open_repo_at(repo_path: &Path) -> Result<Repository> {
+        Repository::open_ext(repo_path, RepositoryOpenFlags::NO_SEARCH, std::iter::empty::<&std::ffi::OsStr>())
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::NotFound => WhiplashError::NotFound(format!(
+                    "'{}' is not a git repository",
+                    repo_path.display()
+                )).into(),
+                _ => anyhow::Error::from(e).context(format!("Failed to open repository '{}'", repo_path.display())),
+            })
+    }
+
+    /// Where new worktrees go by default. Inside a bare repo, `worktrees/` is git's own
+    /// admin directory, so they go in `whiplash-worktrees/` instead.
+    fn worktrees_dir(&self) -> PathBuf {
+        if self.is_bare {
+            self.repo_path.join("whiplash-worktrees")
+        } else {
+            self.repo_path.join("worktrees")
+        }
+    }
+
+    /// Fails for operations that need the main repository's own checkout.
+    fn require_checkout(&self, operation: &str) -> Result<()> {
+        if self.is_bare {
+            return Err(WhiplashError::InvalidInput(format!(
+                "Repository '{}' is bare and has no checkout to {}",
+                self.repo_path.display(),
+                operation
+            )).into());
+        }
+        Ok(())
+    }
+
+    pub fn with_default_signature(mut self, default_signature: Option<CommitSignature>) -> Self {
+        self.default_signature = default_signature;
+        self
+    }
+
+    /// The signature to commit with: `override_signature` if given, then the repo's git
+    /// config, then the configured default. Errors rather than committing as "unknown".
+    fn signature(&self, repo: &Repository, override_signature: Option<&CommitSignature>) -> Result<Signature<'static>> {
+        let from_parts = |signature: &CommitSignature| -> Result<Signature<'static>> {
+            Signature::now(&signature.name, &signature.email).map_err(|e| {
+                WhiplashError::InvalidInput(format!(
+                    "Invalid signature '{} <{}>': {}",
+                    signature.name,
+                    signature.email,
+                    e.message()
+                )).into()
+            })
+        };
+
+        if let Some(signature) = override_signature {
+            return from_parts(signature);
+        }
+        if let Ok(signature) = repo.signature() {
+            return Ok(signature);
+        }
+        match &self.default_signature {
+            Some(signature) => from_parts(signature),
+            None => Err(WhiplashError::InvalidInput(
+                "No commit signature available: set git's user.name and user.email, or configure a default signature".to_string()
+            ).into()),
+        }
+    }
+
+    /// Creates a worktree on `branch`. When `create_branch` is set, a missing branch is
+    /// created from `base` (a branch, tag or revspec, defaulting to the default branch);
+    /// otherwise the branch must already exist. With `dry_run`, every check runs but
+    /// nothing is created; the returned info has status `"dry_run"` and describes what
+    /// would have been created. The worktree goes in `base_dir/<name>` if given, otherwise
+    /// `repo_path/worktrees/<name>` (`repo_path/whiplash-worktrees/<name>` for bare
+    /// repos). `progress` is called with `(completed, total)` file counts while the
+    /// worktree is checked out. If the worktree can't be added, a branch created for it
+    /// is deleted again.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_worktree(
+        &self,
+        name: &str,
+        branch: &str,
+        base: Option<&str>,
+        create_branch: bool,
+        base_dir: Option<&Path>,
+        dry_run: bool,
+        progress: Option<&mut dyn FnMut(usize, usize)>,
+    ) -> Result<GitWorktreeInfo> {
+        Self::validate_worktree_name(name)?;
+        Self::validate_branch_name(branch)?;
+        if let Some(base_dir) = base_dir {
+            Self::validate_base_dir(base_dir)?;
+        }
+
+        let repo = self.open_repo()?;
+        let worktree_path = match base_dir {
+            Some(base_dir) => base_dir.join(name),
+            None => self.worktrees_dir().join(name),
+        };
+
+        let path_in_use = std::fs::read_dir(&worktree_path)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+        if repo.find_worktree(name).is_ok() || path_in_use {
+            return Err(WhiplashError::AlreadyExists(format!("A worktree named '{}' already exists", name)).into());
+        }
         
         // Check if branch exists, create if not
         let branch_ref = format!("refs/heads/{}", branch);
         let branch_exists = repo.find_reference(&branch_ref).is_ok();
-        
+
+        if !branch_exists && !create_branch {
+            return Err(anyhow!("Branch '{}' does not exist", branch));
+        }
+
+        // The commit the worktree will check out: the branch tip, or else the requested
+        // base, the default branch, or HEAD
+        let commit = if branch_exists {
+            repo.find_reference(&branch_ref)
+                .and_then(|reference| reference.peel_to_commit())
+                .with_context(|| format!("Failed to resolve branch '{}'", branch))?
+        } else {
+            match base {
+                Some(base) => repo.revparse_single(base)
+                    .map_err(|e| anyhow!("Could not resolve base '{}': {}", base, e.message()))?
+                    .peel_to_commit()?,
+                None => {
+                    let default_oid = self.default_branch()
+                        .ok()
+                        .and_then(|default_branch| Self::branch_oid(&repo, &default_branch));
+                    let target = match default_oid {
+                        Some(oid) => oid,
+                        None => repo.head()
+                            .context("Failed to resolve HEAD")?
+                            .target()
+                            .ok_or_else(|| anyhow!("HEAD has no target"))?,
+                    };
+                    repo.find_commit(target).with_context(|| format!("Failed to find base commit {}", target))?
+                }
+            }
+        };
+        let head_sha = Self::short_sha(&repo, commit.id());
+        let now = Utc::now();
+
+        if dry_run {
+            return Ok(GitWorktreeInfo {
+                id: String::new(),
+                name: name.to_string(),
+                branch: branch.to_string(),
+                head_sha,
+                path: worktree_path.to_string_lossy().to_string(),
+                status: "dry_run".to_string(),
+                created_at: now,
+                last_activity: now,
+                ahead: 0,
+                behind: 0,
+                has_merge_base: true,
+                upstream: None,
+                up_to_date_with_upstream: None,
+                branch_exists: Some(branch_exists),
+                disk_usage_bytes: None,
+            });
+        }
+
+        // Only the parent is created: libgit2 refuses to add a worktree into an existing directory
+        let worktree_parent = worktree_path.parent().unwrap_or(&worktree_path);
+        std::fs::create_dir_all(worktree_parent)
+            .with_context(|| format!("Failed to create directory '{}'", worktree_parent.display()))?;
+
         if !branch_exists {
-            // Create new branch from HEAD
-            let head = repo.head()?;
-            let target = head.target().ok_or_else(|| anyhow!("HEAD has no target"))?;
-            let commit = repo.find_commit(target)?;
-            repo.branch(branch, &commit, false)?;
+            repo.branch(branch, &commit, false)
+                .with_context(|| format!("Failed to create branch '{}' at {}", branch, commit.id()))?;
         }
         
-        // Create worktree
-        let _worktree = repo.worktree(name, &worktree_path, None)?;
+        // Create worktree checked out on the branch
+        let added = match progress {
+            Some(progress) => self.add_worktree_with_progress(&repo, name, branch, &worktree_path, progress),
+            None => repo.find_reference(&branch_ref)
+                .and_then(|branch_reference| {
+                    let mut add_options = WorktreeAddOptions::new();
+                    add_options.reference(Some(&branch_reference));
+                    repo.worktree(name, &worktree_path, Some(&add_options))
+                })
+                .map(|_| ())
+                .with_context(|| format!("Failed to add worktree '{}' at '{}'", name, worktree_path.display())),
+        };
+        if let Err(e) = added {
+            Self::discard_worktree(&repo, name);
+            if !branch_exists {
+                if let Ok(mut created) = repo.find_branch(branch, BranchType::Local) {
+                    let _ = created.delete();
+                }
+            }
+            return Err(e);
+        }
         
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+
+        // Persist creation metadata so it survives app restarts
+        self.write_metadata(name, &WorktreeMetadata {
+            id: id.clone(),
+            created_at: now,
+            base_dir: base_dir.map(Path::to_path_buf),
+        })
+        .with_context(|| format!("Failed to save metadata for worktree '{}'", name))?;
         
         Ok(GitWorktreeInfo {
             id,
             name: name.to_string(),
             branch: branch.to_string(),
+            head_sha,
             path: worktree_path.to_string_lossy().to_string(),
             status: "active".to_string(),
             created_at: now,
             last_activity: now,
+            ahead: 0,
+            behind: 0,
+            has_merge_base: true,
+            upstream: None,
+            up_to_date_with_upstream: None,
+            branch_exists: None,
+            disk_usage_bytes: None,
         })
     }
 
-    pub fn list_worktrees(&self) -> Result<Vec<GitWorktreeInfo>> {
-        let repo = Repository::open(&self.repo_path)?;
+    /// Creates `new_branch` at the source worktree's HEAD commit and a new worktree on it.
+    /// Only commits are carried over; the source's uncommitted changes stay where they are.
+    pub fn fork_worktree(&self, source_name: &str, new_name: &str, new_branch: &str) -> Result<GitWorktreeInfo> {
+        let repo = self.open_repo()?;
+        let source = repo.find_worktree(source_name).map_err(|_| {
+            WhiplashError::NotFound(format!("Worktree '{}' does not exist", source_name))
+        })?;
+        if !source.path().exists() {
+            return Err(WhiplashError::NotFound(format!(
+                "Worktree '{}' no longer exists at '{}'",
+                source_name,
+                source.path().display()
+            )).into());
+        }
+
+        // Reusing an existing branch would check out its tip rather than the source's HEAD
+        if repo.find_branch(new_branch, BranchType::Local).is_ok() {
+            return Err(WhiplashError::AlreadyExists(format!("Branch '{}' already exists", new_branch)).into());
+        }
+
+        let source_head = self.open_worktree_repo(source_name)?
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .with_context(|| format!("Failed to resolve HEAD of worktree '{}'", source_name))?
+            .id()
+            .to_string();
+
+        self.create_worktree(new_name, new_branch, Some(&source_head), true, None, false, None)
+    }
+
+    /// git2 doesn't expose checkout options for `Repository::worktree`, so its checkout
+    /// can't report progress. Instead the worktree is added on a placeholder branch whose
+    /// commit has an empty tree, so that checkout writes nothing, and HEAD is then moved
+    /// to `branch` and checked out with a progress callback.
+    fn add_worktree_with_progress(
+        &self,
+        repo: &Repository,
+        name: &str,
+        branch: &str,
+        worktree_path: &Path,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<()> {
+        // libgit2 only checks the branch it's given, which is the placeholder here
+        if self.is_branch_checked_out(repo, branch)? {
+            return Err(anyhow!("Branch '{}' is already checked out in another worktree", branch));
+        }
+
+        let empty_tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+        let signature = Signature::now("whiplash", "whiplash@localhost")?;
+        let empty_commit = repo.commit(None, &signature, &signature, "Empty checkout base", &empty_tree, &[])?;
+        let mut placeholder = repo.branch(
+            &format!("whiplash-checkout-{}", Uuid::new_v4()),
+            &repo.find_commit(empty_commit)?,
+            false,
+        )?;
+
+        let checked_out =
+            Self::check_out_from_placeholder(repo, name, branch, worktree_path, placeholder.get(), progress);
+        // The placeholder can only be deleted once no worktree has it checked out
+        if checked_out.is_err() {
+            Self::discard_worktree(repo, name);
+        }
+        let _ = placeholder.delete();
+        checked_out
+    }
+
+    fn check_out_from_placeholder(
+        repo: &Repository,
+        name: &str,
+        branch: &str,
+        worktree_path: &Path,
+        placeholder: &git2::Reference,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<()> {
+        let mut add_options = WorktreeAddOptions::new();
+        add_options.reference(Some(placeholder));
+        let worktree = repo.worktree(name, worktree_path, Some(&add_options))
+            .with_context(|| format!("Failed to add worktree '{}' at '{}'", name, worktree_path.display()))?;
+
+        let worktree_repo = Repository::open_from_worktree(&worktree)
+            .with_context(|| format!("Failed to open worktree '{}'", name))?;
+        worktree_repo.set_head(&format!("refs/heads/{}", branch))?;
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force().progress(|_, completed, total| progress(completed, total));
+        worktree_repo.checkout_head(Some(&mut checkout))
+            .with_context(|| format!("Failed to check out branch '{}' in worktree '{}'", branch, name))?;
+        Ok(())
+    }
+
+    /// Removes a worktree that failed partway through being added, files and all.
+    fn discard_worktree(repo: &Repository, name: &str) {
+        if let Ok(worktree) = repo.find_worktree(name) {
+            let _ = std::fs::remove_dir_all(worktree.path());
+            let _ = worktree.prune(Some(WorktreePruneOptions::new().valid(true).working_tree(true)));
+        }
+    }
+
+    /// Worktree names become directory names under `worktrees/` and in git's admin dir,
+    /// so they're limited to letters, digits, `-`, `_` and `.`, and can't start with a
+    /// dot (which rules out `.`, `..` and the `.whiplash` metadata dir).
+    fn validate_worktree_name(name: &str) -> Result<()> {
+        let invalid = |reason: &str| -> Result<()> {
+            Err(WhiplashError::InvalidInput(format!("Invalid worktree name '{}': {}", name, reason)).into())
+        };
+
+        if name.is_empty() {
+            return invalid("name is empty");
+        }
+        if name.starts_with('.') {
+            return invalid("name can't start with '.'");
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            return invalid("only letters, digits, '-', '_' and '.' are allowed");
+        }
+        Ok(())
+    }
+
+    /// A custom base directory must already exist, and we must be able to create files in it.
+    fn validate_base_dir(base_dir: &Path) -> Result<()> {
+        if !base_dir.is_dir() {
+            return Err(WhiplashError::InvalidInput(format!(
+                "Base directory '{}' does not exist",
+                base_dir.display()
+            )).into());
+        }
+
+        let probe = base_dir.join(format!(".whiplash-write-test-{}", Uuid::new_v4()));
+        if std::fs::write(&probe, b"").is_err() {
+            return Err(WhiplashError::InvalidInput(format!(
+                "Base directory '{}' is not writable",
+                base_dir.display()
+            )).into());
+        }
+        let _ = std::fs::remove_file(probe);
+        Ok(())
+    }
+
+    /// Branch names must make a valid ref under `refs/heads/`, per git's ref-name rules.
+    fn validate_branch_name(branch: &str) -> Result<()> {
+        if branch.is_empty() || !git2::Reference::is_valid_name(&format!("refs/heads/{}", branch)) {
+            return Err(WhiplashError::InvalidInput(format!("Invalid branch name '{}'", branch)).into());
+        }
+        Ok(())
+    }
+
+    /// Lists all worktrees, with ahead/behind counts relative to `base_branch`
+    /// (defaults to the default branch, or the main repo's HEAD if there isn't one).
+    pub fn list_worktrees(&self, base_branch: Option<&str>) -> Result<Vec<GitWorktreeInfo>> {
+        let repo = self.open_repo()?;
         let worktrees = repo.worktrees()?;
+        let base_oid = match base_branch {
+            Some(base_branch) => Some(repo.find_branch(base_branch, BranchType::Local)?.get().peel_to_commit()?.id()),
+            None => self.default_branch()
+                .ok()
+                .and_then(|default_branch| Self::branch_oid(&repo, &default_branch))
+                .or_else(|| repo.head().ok().and_then(|head| head.target())),
+        };
         let mut worktree_infos = Vec::new();
         
-        for worktree_name in &worktrees {
-            if let Some(name) = worktree_name {
-                if let Ok(worktree) = repo.find_worktree(name) {
-                    let path = worktree.path().to_string_lossy().to_string();
-                    let branch = self.get_worktree_branch(&worktree)?;
+        for name in worktrees.iter().flatten() {
+            if let Ok(worktree) = repo.find_worktree(name) {
+                let path = worktree.path().to_string_lossy().to_string();
+                let branch = self.get_worktree_branch(&worktree)?;
+                let head_sha = Repository::open(worktree.path())
+                    .ok()
+                    .and_then(|worktree_repo| worktree_repo.head().ok().and_then(|head| head.target()))
+                    .and_then(|oid| Self::short_sha(&repo, oid));
+                let metadata = self.load_or_init_metadata(name, &worktree)?;
+                let created_at = metadata.created_at;
+                let last_activity = self.get_worktree_last_activity(&worktree).unwrap_or(created_at);
+                let (ahead, behind, has_merge_base) = match base_oid {
+                    Some(base_oid) => self.get_ahead_behind(&repo, &worktree, base_oid),
+                    None => (0, 0, false),
+                };
+                let (upstream, up_to_date_with_upstream) = self.get_upstream_status(&repo, &branch);
                     
-                    let info = GitWorktreeInfo {
-                        id: Uuid::new_v4().to_string(), // TODO: Store persistent IDs
-                        name: name.to_string(),
-                        branch,
-                        path,
-                        status: if worktree.is_locked().is_ok() { "locked".to_string() } else { "active".to_string() },
-                        created_at: Utc::now(), // TODO: Get actual creation time
-                        last_activity: Utc::now(), // TODO: Get actual last activity
-                    };
+                let info = GitWorktreeInfo {
+                    id: metadata.id,
+                    name: name.to_string(),
+                    branch: branch.clone(),
+                    head_sha,
+                    path,
+                    status: match worktree.is_locked() {
+                        Ok(WorktreeLockStatus::Locked(_)) => "locked".to_string(),
+                        _ => "active".to_string(),
+                    },
+                    created_at,
+                    last_activity,
+                    ahead,
+                    behind,
+                    has_merge_base,
+                    upstream,
+                    up_to_date_with_upstream,
+                    branch_exists: None,
+                    disk_usage_bytes: None,
+                };
                     
-                    worktree_infos.push(info);
-                }
+                worktree_infos.push(info);
             }
         }
         
         Ok(worktree_infos)
     }
 
-    pub fn delete_worktree(&self, name: &str) -> Result<()> {
-        let repo = Repository::open(&self.repo_path)?;
-        let worktree = repo.find_worktree(name)?;
+    pub fn delete_worktree(&self, name: &str, force: bool, delete_branch: bool) -> Result<WorktreeDeletion> {
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(name)
+            .with_context(|| format!("Failed to find worktree '{}'", name))?;
+
+        // Refuse to throw away uncommitted work unless explicitly forced
+        if !force && worktree.path().exists() {
+            let dirty_files = self.dirty_files(name)
+                .with_context(|| format!("Failed to read the status of worktree '{}'", name))?;
+            if !dirty_files.is_empty() {
+                return Err(anyhow!(
+                    "Worktree '{}' has {} uncommitted file(s); use force to delete anyway",
+                    name,
+                    dirty_files.len()
+                ));
+            }
+        }
+
+        // Resolve the branch while the worktree still exists
+        let branch = if worktree.path().exists() {
+            Some(self.get_worktree_branch(&worktree)?)
+                .filter(|branch| branch != "unknown" && branch != DETACHED_BRANCH)
+        } else {
+            None
+        };
         
         // Remove worktree files
         if let Ok(path) = worktree.path().canonicalize() {
-            std::fs::remove_dir_all(path)?;
+            std::fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove directory '{}'", path.display()))?;
         }
         
         // Prune the worktree
-        worktree.prune(None)?;
-        
-        Ok(())
-    }
+        worktree.prune(None)
+            .with_context(|| format!("Failed to prune worktree '{}'", name))?;
 
-    pub fn get_worktree_status(&self, name: &str) -> Result<Vec<String>> {
-        let repo = Repository::open(&self.repo_path)?;
-        let worktree = repo.find_worktree(name)?;
-        let worktree_path = worktree.path();
-        
-        let worktree_repo = Repository::open(worktree_path)?;
-        let mut status_list = Vec::new();
-        
-        let statuses = worktree_repo.statuses(None)?;
-        for entry in statuses.iter() {
-            let status = entry.status();
-            let file_path = entry.path().unwrap_or("unknown");
-            
-            let status_str = match status {
-                s if s.is_wt_new() => format!("new: {}", file_path),
-                s if s.is_wt_modified() => format!("modified: {}", file_path),
-                s if s.is_wt_deleted() => format!("deleted: {}", file_path),
-                s if s.is_wt_renamed() => format!("renamed: {}", file_path),
-                s if s.is_wt_typechange() => format!("typechange: {}", file_path),
-                s if s.is_index_new() => format!("staged new: {}", file_path),
-                s if s.is_index_modified() => format!("staged modified: {}", file_path),
-                s if s.is_index_deleted() => format!("staged deleted: {}", file_path),
-                _ => format!("unknown: {}", file_path),
-            };
-            
-            status_list.push(status_str);
+        // Drop the persisted metadata, if any
+        let metadata_path = self.metadata_path(name);
+        if metadata_path.exists() {
+            std::fs::remove_file(&metadata_path)
+                .with_context(|| format!("Failed to remove metadata '{}'", metadata_path.display()))?;
         }
-        
-        Ok(status_list)
-    }
 
-    pub fn get_modified_files(&self, name: &str) -> Result<Vec<String>> {
-        let repo = Repository::open(&self.repo_path)?;
-        let worktree = repo.find_worktree(name)?;
-        let worktree_path = worktree.path();
-        
-        let worktree_repo = Repository::open(worktree_path)?;
-        let mut modified_files = Vec::new();
-        
-        let statuses = worktree_repo.statuses(None)?;
-        for entry in statuses.iter() {
-            let status = entry.status();
-            if status.is_wt_modified() || status.is_wt_new() || status.is_index_modified() || status.is_index_new() {
-                if let Some(file_path) = entry.path() {
-                    modified_files.push(file_path.to_string());
-                }
+        let mut branch_deleted = false;
+        if let Some(branch_name) = branch.as_deref() {
+            if delete_branch && self.is_branch_deletable(&repo, branch_name)? {
+                repo.find_branch(branch_name, BranchType::Local)
+                    .and_then(|mut branch| branch.delete())
+                    .with_context(|| format!("Failed to delete branch '{}'", branch_name))?;
+                branch_deleted = true;
             }
         }
         
-        Ok(modified_files)
+        Ok(WorktreeDeletion {
+            name: name.to_string(),
+            branch,
+            branch_deleted,
+        })
     }
 
-    fn get_worktree_branch(&self, worktree: &Worktree) -> Result<String> {
-        let worktree_path = worktree.path();
-        let worktree_repo = Repository::open(worktree_path)?;
-        
-        if let Ok(head) = worktree_repo.head() {
-            if let Some(branch_name) = head.shorthand() {
-                return Ok(branch_name.to_string());
-            }
+    /// A branch can be deleted when it isn't a default branch and nothing else has it checked out.
+    fn is_branch_deletable(&self, repo: &Repository, branch: &str) -> Result<bool> {
+        if matches!(branch, "main" | "master" | "trunk") {
+            return Ok(false);
         }
-        
-        Ok("unknown".to_string())
-    }
-}
 
-#[tauri::command]
-pub async fn create_worktree(repo_path: String, name: String, branch: String) -> Result<GitWorktreeInfo, String> {
-    let manager = GitWorktreeManager::new(repo_path).map_err(|e| e.to_string())?;
-    manager.create_worktree(&name, &branch).map_err(|e| e.to_string())
-}
+        if self.default_branch().ok().as_deref() == Some(branch) {
+            return Ok(false);
+        }
 
-#[tauri::command]
-pub async fn list_worktrees(repo_path: String) -> Result<Vec<GitWorktreeInfo>, String> {
-    let manager = GitWorktreeManager::new(repo_path).map_err(|e| e.to_string())?;
-    manager.list_worktrees().map_err(|e| e.to_string())
-}
+        if repo.head().ok().and_then(|head| head.shorthand().map(str::to_string)).as_deref() == Some(branch) {
+            return Ok(false);
+        }
 
-#[tauri::command]
-pub async fn delete_worktree(repo_path: String, name: String) -> Result<(), String> {
-    let manager = GitWorktreeManager::new(repo_path).map_err(|e| e.to_string())?;
-    manager.delete_worktree(&name).map_err(|e| e.to_string())
-}
+        Ok(!self.is_branch_checked_out(repo, branch)?)
+    }
 
-#[tauri::command]
-pub async fn get_worktree_status(repo_path: String, name: String) -> Result<Vec<String>, String> {
-    let manager = GitWorktreeManager::new(repo_path).map_err(|e| e.to_string())?;
-    manager.get_worktree_status(&name).map_err(|e| e.to_string())
-}
+    /// Whether `branch` is checked out in the main working tree or any existing worktree.
+    fn is_branch_checked_out(&self, repo: &Repository, branch: &str) -> Result<bool> {
+        let head_branch = repo.head().ok().and_then(|head| head.shorthand().map(str::to_string));
+        if !repo.is_bare() && head_branch.as_deref() == Some(branch) {
+            return Ok(true);
+        }
+
+        for worktree_name in repo.worktrees()?.iter().flatten() {
+            if let Ok(worktree) = repo.find_worktree(worktree_name) {
+                if worktree.path().exists() && self.get_worktree_branch(&worktree)? == branch {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Zips a worktree's working directory, minus `.git`, to `dest_path`. Ignored files
+    /// are kept, since build outputs are often what's worth saving. With
+    /// `delete_after`, the worktree is then force-deleted (its branch is kept).
+    pub fn archive_worktree(&self, name: &str, dest_path: &Path, delete_after: bool) -> Result<WorktreeArchive> {
+        let worktree_path = self.worktree_path(name)?;
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(dest_path)?);
+        let walker = walkdir::WalkDir::new(&worktree_path)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        for entry in walker {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(&worktree_path)?;
+            let entry_name = relative.to_string_lossy().replace('\\', "/");
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+
+            if entry.file_type().is_dir() {
+                zip.add_directory(entry_name, options)?;
+            } else if entry.file_type().is_symlink() {
+                let target = std::fs::read_link(entry.path())?;
+                zip.add_symlink(entry_name, target.to_string_lossy(), options)?;
+            } else {
+                zip.start_file(entry_name, options)?;
+                std::io::copy(&mut std::fs::File::open(entry.path())?, &mut zip)?;
+            }
+        }
+        zip.finish()?;
+
+        let size_bytes = std::fs::metadata(dest_path)?.len();
+        if delete_after {
+            self.delete_worktree(name, true, false)?;
+        }
+
+        Ok(WorktreeArchive {
+            archive_path: dest_path.to_string_lossy().to_string(),
+            size_bytes,
+            deleted: delete_after,
+        })
+    }
+
+    /// Renames a worktree, moving its directory next to the old one and rewriting the
+    /// admin files that link it to the main repo, since git has no native rename.
+    /// When `new_branch` is given, the worktree's branch is renamed as well. Everything
+    /// that can be checked is checked up front, and the move is undone if a later step
+    /// fails, so a worktree is never left half renamed.
+    pub fn rename_worktree(&self, old_name: &str, new_name: &str, new_branch: Option<&str>) -> Result<GitWorktreeInfo> {
+        Self::validate_worktree_name(new_name)?;
+        if let Some(new_branch) = new_branch {
+            Self::validate_branch_name(new_branch)?;
+        }
+
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(old_name)?;
+        if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+            return Err(WhiplashError::Conflict(format!(
+                "Worktree '{}' is locked{}; unlock it before renaming",
+                old_name,
+                reason.map(|reason| format!(" ({})", reason)).unwrap_or_default()
+            )).into());
+        }
+
+        let old_admin_dir = repo.path().join("worktrees").join(old_name);
+        let new_admin_dir = repo.path().join("worktrees").join(new_name);
+        if repo.find_worktree(new_name).is_ok() || new_admin_dir.exists() {
+            return Err(WhiplashError::AlreadyExists(format!("A worktree named '{}' already exists", new_name)).into());
+        }
+
+        let old_path = worktree.path().to_path_buf();
+        let new_path = old_path
+            .parent()
+            .ok_or_else(|| anyhow!("Worktree '{}' has no parent directory", old_name))?
+            .join(new_name);
+        if new_path.exists() {
+            return Err(anyhow!("Path '{}' already exists", new_path.display()));
+        }
+
+        let branch = self.get_worktree_branch(&worktree)?;
+        drop(worktree);
+        if let Some(new_branch) = new_branch {
+            if branch == DETACHED_BRANCH || repo.find_branch(&branch, BranchType::Local).is_err() {
+                return Err(WhiplashError::InvalidInput(format!(
+                    "Worktree '{}' isn't on a local branch, so its branch can't be renamed",
+                    old_name
+                )).into());
+            }
+            if repo.find_branch(new_branch, BranchType::Local).is_ok() {
+                return Err(WhiplashError::AlreadyExists(format!("Branch '{}' already exists", new_branch)).into());
+            }
+        }
+
+        // Move the working directory and the admin dir, then repoint them at each other
+        std::fs::rename(&old_path, &new_path)
+            .with_context(|| format!("Failed to move '{}' to '{}'", old_path.display(), new_path.display()))?;
+        let undo_move = || {
+            let _ = std::fs::rename(&new_path, &old_path);
+            if new_admin_dir.exists() {
+                let _ = Self::move_admin_dir(&new_admin_dir, &old_admin_dir, &old_path);
+            }
+        };
+        if let Err(e) = Self::move_admin_dir(&old_admin_dir, &new_admin_dir, &new_path) {
+            undo_move();
+            return Err(e.context(format!("Failed to move the admin dir of worktree '{}'", old_name)));
+        }
+
+        if let Some(new_branch) = new_branch {
+            let renamed = repo.find_branch(&branch, BranchType::Local)
+                .and_then(|mut branch| branch.rename(new_branch, false).map(|_| ()));
+            if let Err(e) = renamed {
+                undo_move();
+                return Err(anyhow::Error::from(e)
+                    .context(format!("Failed to rename branch '{}' to '{}'", branch, new_branch)));
+            }
+        }
+
+        let old_metadata_path = self.metadata_path(old_name);
+        if old_metadata_path.exists() {
+            std::fs::rename(old_metadata_path, self.metadata_path(new_name))?;
+        }
+
+        self.list_worktrees(None)?
+            .into_iter()
+            .find(|info| info.name == new_name)
+            .ok_or_else(|| anyhow!("Worktree '{}' not found after rename", new_name))
+    }
+
+    /// Moves a worktree's admin dir and points it and the worktree's `.git` file at each
+    /// other.
+    fn move_admin_dir(from: &Path, to: &Path, worktree_path: &Path) -> Result<()> {
+        std::fs::rename(from, to)?;
+        std::fs::write(worktree_path.join(".git"), format!("gitdir: {}\n", to.display()))?;
+        std::fs::write(to.join("gitdir"), format!("{}\n", worktree_path.join(".git").display()))?;
+        Ok(())
+    }
+
+    pub fn lock_worktree(&self, name: &str, reason: &str) -> Result<()> {
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(name)?;
+        worktree.lock(Some(reason))?;
+        Ok(())
+    }
+
+    pub fn unlock_worktree(&self, name: &str) -> Result<()> {
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(name)?;
+        worktree.unlock()?;
+        Ok(())
+    }
+
+    /// Prunes worktrees whose directories no longer exist, e.g. after being deleted by
+    /// hand, and returns their names. Locked worktrees are kept, as with `git worktree prune`.
+    pub fn prune_worktrees(&self) -> Result<Vec<String>> {
+        let repo = self.open_repo()?;
+        let mut pruned = Vec::new();
+
+        for name in repo.worktrees()?.iter().flatten() {
+            let worktree = match repo.find_worktree(name) {
+                Ok(worktree) => worktree,
+                Err(_) => continue,
+            };
+
+            if worktree.validate().is_ok() && worktree.path().exists() {
+                continue;
+            }
+            if let Ok(WorktreeLockStatus::Locked(_)) = worktree.is_locked() {
+                continue;
+            }
+
+            worktree.prune(Some(WorktreePruneOptions::new().valid(true)))?;
+
+            let metadata_path = self.metadata_path(name);
+            if metadata_path.exists() {
+                std::fs::remove_file(metadata_path)?;
+            }
+            pruned.push(name.to_string());
+        }
+
+        Ok(pruned)
+    }
+
+    pub fn get_worktree_status(&self, name: &str) -> Result<Vec<FileStatus>> {
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(name)?;
+        let worktree_path = worktree.path();
+        
+        let worktree_repo = Repository::open(worktree_path)?;
+        let mut status_list = Vec::new();
+        
+        let statuses = worktree_repo.statuses(None)?;
+        for entry in statuses.iter() {
+            let status = entry.status();
+            let file_path = entry.path().unwrap_or("unknown");
+            
+            status_list.push(FileStatus {
+                path: file_path.to_string(),
+                states: Self::status_labels(status).into_iter().map(str::to_string).collect(),
+            });
+        }
+        
+        Ok(status_list)
+    }
+
+    /// Files with uncommitted changes in a worktree, untracked files included. Ignored
+    /// files such as build output don't count.
+    fn dirty_files(&self, name: &str) -> Result<Vec<String>> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let mut status_options = StatusOptions::new();
+        status_options.include_ignored(false).include_untracked(true);
+
+        let statuses = worktree_repo.statuses(Some(&mut status_options))?;
+        Ok(statuses.iter().filter_map(|entry| entry.path().map(str::to_string)).collect())
+    }
+
+    /// Describes every state a status entry is in, index states first, so a file that is
+    /// both staged and modified again in the working tree reports both.
+    fn status_labels(status: git2::Status) -> Vec<&'static str> {
+        if status.is_conflicted() {
+            return vec!["conflicted"];
+        }
+        if status.is_ignored() {
+            return vec!["ignored"];
+        }
+
+        let labels: Vec<&'static str> = [
+            (status.is_index_new(), "staged new"),
+            (status.is_index_modified(), "staged modified"),
+            (status.is_index_deleted(), "staged deleted"),
+            (status.is_index_renamed(), "staged renamed"),
+            (status.is_index_typechange(), "staged typechange"),
+            (status.is_wt_new(), "new"),
+            (status.is_wt_modified(), "modified"),
+            (status.is_wt_deleted(), "deleted"),
+            (status.is_wt_renamed(), "renamed"),
+            (status.is_wt_typechange(), "typechange"),
+        ]
+        .into_iter()
+        .filter(|(is_set, _)| *is_set)
+        .map(|(_, label)| label)
+        .collect();
+
+        if labels.is_empty() {
+            vec!["unknown"]
+        } else {
+            labels
+        }
+    }
+
+    /// Lists changed files in a worktree whose kind of change is in `categories`.
+    /// Ignored files are never included.
+    pub fn get_modified_files(&self, name: &str, categories: ChangeCategories) -> Result<Vec<String>> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let mut modified_files: Vec<String> = Vec::new();
+
+        let include_untracked = categories.contains(ChangeCategories::UNTRACKED);
+        let detect_renames = categories.contains(ChangeCategories::RENAMED);
+        let mut status_options = StatusOptions::new();
+        status_options
+            .include_ignored(false)
+            .include_untracked(include_untracked)
+            .recurse_untracked_dirs(include_untracked)
+            .renames_head_to_index(detect_renames)
+            .renames_index_to_workdir(detect_renames);
+
+        let statuses = worktree_repo.statuses(Some(&mut status_options))?;
+        for entry in statuses.iter() {
+            let status = entry.status();
+            let modified = status.is_wt_modified() || status.is_index_modified()
+                || status.is_wt_typechange() || status.is_index_typechange();
+            let renamed = status.is_wt_renamed() || status.is_index_renamed();
+
+            let counts = (modified && categories.contains(ChangeCategories::MODIFIED))
+                || (status.is_index_new() && categories.contains(ChangeCategories::ADDED))
+                || (status.is_wt_new() && include_untracked)
+                || ((status.is_wt_deleted() || status.is_index_deleted())
+                    && categories.contains(ChangeCategories::DELETED))
+                || (renamed && detect_renames);
+            if !counts {
+                continue;
+            }
+
+            // A rename's entry path is the old one; its new path is only on the deltas
+            let mut paths: Vec<&str> = entry.path().into_iter().collect();
+            if renamed {
+                for delta in [entry.head_to_index(), entry.index_to_workdir()].into_iter().flatten() {
+                    paths.extend(delta.new_file().path().and_then(Path::to_str));
+                }
+            }
+
+            for file_path in paths {
+                if !modified_files.iter().any(|existing| existing == file_path) {
+                    modified_files.push(file_path.to_string());
+                }
+            }
+        }
+
+        Ok(modified_files)
+    }
+
+    /// Merges the worktree's branch into `target_branch` in memory and returns the
+    /// paths that would conflict. Neither the index nor any working tree is touched.
+    pub fn check_merge_conflicts(&self, name: &str, target_branch: &str) -> Result<Vec<String>> {
+        let repo = self.open_repo()?;
+        let worktree_repo = self.open_worktree_repo(name)?;
+
+        let worktree_commit = repo.find_commit(worktree_repo.head()?.peel_to_commit()?.id())?;
+        let target_commit = repo.find_branch(target_branch, BranchType::Local)?
+            .get()
+            .peel_to_commit()?;
+
+        let index = repo.merge_commits(&target_commit, &worktree_commit, None)?;
+        Self::conflicted_paths(&index)
+    }
+
+    fn conflicted_paths(index: &Index) -> Result<Vec<String>> {
+        let mut conflicting_files = Vec::new();
+
+        if index.has_conflicts() {
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+                if let Some(entry) = entry {
+                    conflicting_files.push(String::from_utf8_lossy(&entry.path).to_string());
+                }
+            }
+        }
+
+        Ok(conflicting_files)
+    }
+
+    /// Rebases the worktree's branch onto `onto_branch`, one commit at a time. On
+    /// conflicts the rebase is left in progress and the conflicting step is returned;
+    /// resolve and stage the files, then call `continue_rebase`, or call `abort_rebase`.
+    pub fn rebase_worktree(&self, name: &str, onto_branch: &str) -> Result<RebaseResult> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        if Self::is_rebasing(&worktree_repo) {
+            return Err(WhiplashError::Conflict(format!(
+                "A rebase is already in progress in worktree '{}'; continue or abort it first",
+                name
+            )).into());
+        }
+
+        let mut status_options = StatusOptions::new();
+        status_options.include_untracked(false).include_ignored(false);
+        if !worktree_repo.statuses(Some(&mut status_options))?.is_empty() {
+            return Err(anyhow!("Worktree '{}' has uncommitted changes; commit or stash them before rebasing", name));
+        }
+
+        let onto_oid = Self::branch_oid(&worktree_repo, onto_branch)
+            .ok_or_else(|| WhiplashError::NotFound(format!("Branch '{}' does not exist", onto_branch)))?;
+        let onto = worktree_repo.find_annotated_commit(onto_oid)?;
+
+        let mut rebase = worktree_repo.rebase(None, Some(&onto), None, None)?;
+        self.run_rebase(&worktree_repo, &mut rebase, false)
+    }
+
+    /// Resumes a rebase stopped on conflicts, once they've been resolved and staged.
+    pub fn continue_rebase(&self, name: &str) -> Result<RebaseResult> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let mut rebase = worktree_repo.open_rebase(None)?;
+        self.run_rebase(&worktree_repo, &mut rebase, true)
+    }
+
+    /// Cancels an in-progress rebase, restoring the branch to where it was before.
+    pub fn abort_rebase(&self, name: &str) -> Result<()> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        worktree_repo.open_rebase(None)?.abort()?;
+        Ok(())
+    }
+
+    /// Aborts whatever merge, rebase, cherry-pick or similar is in progress in the main
+    /// repo, or in worktree `name` if given, and returns its working tree and index to HEAD.
+    /// Rebases are aborted properly, restoring the original branch; anything else is hard
+    /// reset to HEAD. Untracked files are left alone.
+    pub fn abort_git_operation(&self, name: Option<&str>) -> Result<AbortedOperation> {
+        let repo = match name {
+            Some(name) => self.open_worktree_repo(name)?,
+            None => {
+                self.require_checkout("clean up")?;
+                self.open_repo()?
+            }
+        };
+
+        let operation = match repo.state() {
+            RepositoryState::Clean => "none",
+            RepositoryState::Merge => "merge",
+            RepositoryState::Revert | RepositoryState::RevertSequence => "revert",
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => "cherry_pick",
+            RepositoryState::Bisect => "bisect",
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => "rebase",
+            RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => "apply_mailbox",
+        };
+
+        let rebase_aborted = operation == "rebase"
+            && repo.open_rebase(None).and_then(|mut rebase| rebase.abort()).is_ok();
+        // Rebases git2 can't open (e.g. ones started by the git CLI) fall through to a reset
+        if operation != "none" && !rebase_aborted {
+            let head = repo.head()
+                .and_then(|head| head.peel_to_commit())
+                .context("Failed to resolve HEAD")?;
+            repo.reset(head.as_object(), ResetType::Hard, None)
+                .context("Failed to reset to HEAD")?;
+            repo.cleanup_state().context("Failed to clear the operation state")?;
+        }
+
+        let head_sha = repo.head().ok()
+            .and_then(|head| head.target())
+            .and_then(|oid| Self::short_sha(&repo, oid));
+        Ok(AbortedOperation {
+            operation: operation.to_string(),
+            head_sha,
+        })
+    }
+
+    fn is_rebasing(repo: &Repository) -> bool {
+        matches!(
+            repo.state(),
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge
+        )
+    }
+
+    /// Applies the remaining steps of `rebase`, stopping at the first one that conflicts.
+    /// With `resume`, the current step was stopped on earlier and is committed first.
+    fn run_rebase(&self, repo: &Repository, rebase: &mut Rebase, resume: bool) -> Result<RebaseResult> {
+        let signature = self.signature(repo, None)?;
+        let total_steps = rebase.len();
+
+        let stopped = |rebase: &mut Rebase, index: &Index| -> Result<RebaseResult> {
+            let current_step = rebase.operation_current();
+            Ok(RebaseResult {
+                status: "conflicts".to_string(),
+                head_sha: None,
+                current_step: current_step.map(|step| step + 1),
+                total_steps,
+                conflicting_commit: current_step
+                    .and_then(|step| rebase.nth(step))
+                    .map(|operation| operation.id().to_string()),
+                conflicts: Self::conflicted_paths(index)?,
+            })
+        };
+
+        if resume && rebase.operation_current().is_some() {
+            let index = repo.index()?;
+            if index.has_conflicts() {
+                return stopped(rebase, &index);
+            }
+            Self::commit_rebase_step(rebase, &signature)?;
+        }
+
+        while let Some(operation) = rebase.next() {
+            operation?;
+            let index = repo.index()?;
+            if index.has_conflicts() {
+                return stopped(rebase, &index);
+            }
+            Self::commit_rebase_step(rebase, &signature)?;
+        }
+
+        rebase.finish(Some(&signature))?;
+        let head_oid = repo.head()?.peel_to_commit()?.id();
+
+        Ok(RebaseResult {
+            status: "completed".to_string(),
+            head_sha: Some(head_oid.to_string()),
+            current_step: None,
+            total_steps,
+            conflicting_commit: None,
+            conflicts: Vec::new(),
+        })
+    }
+
+    fn commit_rebase_step(rebase: &mut Rebase, signature: &git2::Signature) -> Result<()> {
+        match rebase.commit(None, signature, None) {
+            Ok(_) => Ok(()),
+            // The commit's changes are already upstream, so it's dropped
+            Err(e) if e.code() == git2::ErrorCode::Applied => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Merges the worktree's branch into `target_branch`, checking the target out in the
+    /// main repo. The merge is worked out in memory first, so a conflicting or rejected
+    /// merge leaves the repo untouched.
+    pub fn merge_worktree(&self, name: &str, target_branch: &str, strategy: MergeStrategy) -> Result<MergeResult> {
+        self.require_checkout("merge into")?;
+        let repo = self.open_repo()?;
+
+        // Don't clobber uncommitted work in the main checkout
+        let mut status_options = StatusOptions::new();
+        status_options.include_untracked(false).include_ignored(false);
+        let statuses = repo.statuses(Some(&mut status_options))
+            .context("Failed to read the main repository's status")?;
+        if !statuses.is_empty() {
+            return Err(anyhow!("Main repository has uncommitted changes; commit or stash them before merging"));
+        }
+
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let source_commit = worktree_repo.head()
+            .and_then(|head| head.peel_to_commit())
+            .and_then(|commit| repo.find_commit(commit.id()))
+            .with_context(|| format!("Failed to resolve HEAD of worktree '{}'", name))?;
+        let target_ref = format!("refs/heads/{}", target_branch);
+        let target_reference = repo.find_reference(&target_ref)
+            .with_context(|| format!("Failed to resolve target branch '{}'", target_branch))?;
+        let target_commit = target_reference.peel_to_commit()
+            .with_context(|| format!("Failed to resolve target branch '{}'", target_branch))?;
+
+        let annotated = repo.find_annotated_commit(source_commit.id())?;
+        let (analysis, _) = repo.merge_analysis_for_ref(&target_reference, &[&annotated])
+            .with_context(|| format!("Failed to analyze merging '{}' into '{}'", name, target_branch))?;
+
+        if analysis.is_up_to_date() {
+            return Ok(MergeResult {
+                status: "up_to_date".to_string(),
+                commit: Some(target_commit.id().to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        if analysis.is_fast_forward() && strategy != MergeStrategy::NoFastForward {
+            let message = format!("Fast-forward {} to {}", target_branch, source_commit.id());
+            Self::advance_checked_out_branch(&repo, &target_ref, &source_commit, &message)
+                .with_context(|| format!("Failed to fast-forward '{}'", target_branch))?;
+
+            return Ok(MergeResult {
+                status: "fast_forward".to_string(),
+                commit: Some(source_commit.id().to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        if strategy == MergeStrategy::FastForwardOnly {
+            return Err(anyhow!("Cannot fast-forward {} to the worktree branch", target_branch));
+        }
+
+        let mut index = repo.merge_commits(&target_commit, &source_commit, None)
+            .with_context(|| format!("Failed to merge '{}' into '{}'", name, target_branch))?;
+        if index.has_conflicts() {
+            return Ok(MergeResult {
+                status: "conflicts".to_string(),
+                commit: None,
+                conflicts: Self::conflicted_paths(&index)?,
+            });
+        }
+
+        let tree = index.write_tree_to(&repo)
+            .and_then(|tree_oid| repo.find_tree(tree_oid))
+            .context("Failed to write the merged tree")?;
+        let signature = self.signature(&repo, None)?;
+        let branch = self.get_worktree_branch(&repo.find_worktree(name)?)?;
+        let message = format!("Merge branch '{}' into {}", branch, target_branch);
+        let merge_commit = repo.commit(
+            None,
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &[&target_commit, &source_commit],
+        )
+        .and_then(|oid| repo.find_commit(oid))
+        .with_context(|| format!("Failed to create the merge commit on '{}'", target_branch))?;
+        Self::advance_checked_out_branch(&repo, &target_ref, &merge_commit, &message)
+            .with_context(|| format!("Failed to check out '{}' after merging", target_branch))?;
+        let merge_commit = merge_commit.id();
+
+        Ok(MergeResult {
+            status: "merged".to_string(),
+            commit: Some(merge_commit.to_string()),
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// Checks `commit` out in the main repo, then points `branch_ref` at it and makes it
+    /// HEAD. Checking out first means a failed checkout leaves the branch where it was.
+    fn advance_checked_out_branch(repo: &Repository, branch_ref: &str, commit: &Commit, message: &str) -> Result<(), git2::Error> {
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        repo.find_reference(branch_ref)?.set_target(commit.id(), message)?;
+        repo.set_head(branch_ref)
+    }
+
+    /// Sums the sizes of the files in a worktree, skipping `.git` and not following
+    /// symlinks. Results are cached for `DISK_USAGE_TTL` since walking can be slow.
+    pub fn worktree_disk_usage(&self, name: &str) -> Result<u64> {
+        let worktree_path = self.worktree_path(name)?;
+
+        if let Ok(cache) = disk_usage_cache().lock() {
+            if let Some((measured_at, bytes)) = cache.get(&worktree_path) {
+                if measured_at.elapsed() < DISK_USAGE_TTL {
+                    return Ok(*bytes);
+                }
+            }
+        }
+
+        let bytes = walkdir::WalkDir::new(&worktree_path)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        if let Ok(mut cache) = disk_usage_cache().lock() {
+            cache.insert(worktree_path, (Instant::now(), bytes));
+        }
+        Ok(bytes)
+    }
+
+    /// Stashes a worktree's uncommitted changes, untracked files included, and returns
+    /// the stash commit's id. `refs/stash` is shared by all worktrees, so the message is
+    /// tagged with the worktree name to tell their stashes apart.
+    pub fn stash_worktree(&self, name: &str, message: Option<&str>) -> Result<String> {
+        let mut worktree_repo = self.open_worktree_repo(name)?;
+        let signature = self.signature(&worktree_repo, None)?;
+        let message = format!("{} {}", Self::stash_marker(name), message.unwrap_or("whiplash stash"));
+
+        let oid = worktree_repo.stash_save(&signature, &message, Some(StashFlags::INCLUDE_UNTRACKED))?;
+        Ok(oid.to_string())
+    }
+
+    /// Applies and drops a worktree's stash: `stash_id` if given, otherwise its most recent
+    /// one. Returns the id of the stash that was popped.
+    pub fn stash_pop_worktree(&self, name: &str, stash_id: Option<&str>) -> Result<String> {
+        let stashes = self.list_worktree_stashes(name)?;
+        let stash = match stash_id {
+            Some(stash_id) => stashes.into_iter().find(|stash| stash.id == stash_id),
+            None => stashes.into_iter().next(),
+        }
+        .ok_or_else(|| anyhow!("No stash found for worktree '{}'", name))?;
+
+        let mut worktree_repo = self.open_worktree_repo(name)?;
+        worktree_repo.stash_pop(stash.index, None)?;
+        Ok(stash.id)
+    }
+
+    /// Lists the stashes made from a worktree, most recent first.
+    pub fn list_worktree_stashes(&self, name: &str) -> Result<Vec<StashInfo>> {
+        let mut worktree_repo = self.open_worktree_repo(name)?;
+        let marker = Self::stash_marker(name);
+        let mut stashes = Vec::new();
+
+        worktree_repo.stash_foreach(|index, message, oid| {
+            if message.contains(&marker) {
+                stashes.push(StashInfo {
+                    index,
+                    id: oid.to_string(),
+                    message: message.to_string(),
+                });
+            }
+            true
+        })?;
+
+        Ok(stashes)
+    }
+
+    fn stash_marker(name: &str) -> String {
+        format!("[whiplash:{}]", name)
+    }
+
+    /// Commits the worktree's index to its current branch, optionally staging every
+    /// change first, as `signature` if given (see `signature`). Returns the new commit's SHA.
+    pub fn commit_worktree(
+        &self,
+        name: &str,
+        message: &str,
+        stage_all: bool,
+        signature: Option<&CommitSignature>,
+    ) -> Result<String> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let mut index = worktree_repo.index()?;
+
+        if stage_all {
+            index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+            // add_all doesn't pick up deletions
+            index.update_all(["*"].iter(), None)?;
+            index.write()?;
+        }
+
+        let tree_oid = index.write_tree()?;
+        let parent = worktree_repo.head()?.peel_to_commit()?;
+        if parent.tree_id() == tree_oid {
+            return Err(anyhow!("Nothing to commit in worktree '{}'", name));
+        }
+
+        let tree = worktree_repo.find_tree(tree_oid)?;
+        let signature = self.signature(&worktree_repo, signature)?;
+        let commit_oid = worktree_repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &[&parent],
+        )?;
+
+        Ok(commit_oid.to_string())
+    }
+
+    /// Summarizes every worktree. `running_tasks` is left at 0 for the caller to fill in,
+    /// since tasks live in the `ClaudeRunner`.
+    pub fn get_worktree_summaries(&self) -> Result<Vec<WorktreeSummary>> {
+        let summaries = self.list_worktrees(None)?
+            .into_iter()
+            .map(|info| WorktreeSummary {
+                dirty_files: self.dirty_files(&info.name).map(|files| files.len()).unwrap_or(0),
+                last_commit: self.get_worktree_log(&info.name, 1).ok().and_then(|log| log.into_iter().next()),
+                running_tasks: 0,
+                name: info.name,
+                branch: info.branch,
+                head_sha: info.head_sha,
+                ahead: info.ahead,
+                behind: info.behind,
+            })
+            .collect();
+
+        Ok(summaries)
+    }
+
+    /// Lists local branches, then remote-tracking ones if `include_remote` is set, each sorted by name.
+    pub fn list_branches(&self, include_remote: bool) -> Result<Vec<BranchInfo>> {
+        let repo = self.open_repo()?;
+        let head_branch = repo.head().ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(str::to_string));
+        let base_oid = self.default_branch()
+            .ok()
+            .and_then(|default_branch| Self::branch_oid(&repo, &default_branch));
+
+        let worktree_branches: Vec<String> = repo.worktrees()?
+            .iter()
+            .flatten()
+            .filter_map(|name| repo.find_worktree(name).ok())
+            .filter(|worktree| worktree.path().exists())
+            .filter_map(|worktree| self.get_worktree_branch(&worktree).ok())
+            .collect();
+
+        let branch_type = if include_remote { None } else { Some(BranchType::Local) };
+        let mut branches = Vec::new();
+        for branch in repo.branches(branch_type)? {
+            let (branch, branch_type) = branch?;
+            let name = match branch.name()? {
+                Some(name) => name.to_string(),
+                None => continue, // Not valid UTF-8
+            };
+            let is_remote = matches!(branch_type, BranchType::Remote);
+            // `origin/HEAD` is a pointer to another branch, not a branch of its own
+            if is_remote && branch.get().symbolic_target().is_some() {
+                continue;
+            }
+
+            let (ahead, behind) = match (branch.get().target(), base_oid) {
+                (Some(oid), Some(base_oid)) => repo.graph_ahead_behind(oid, base_oid).unwrap_or((0, 0)),
+                _ => (0, 0),
+            };
+            let upstream = if is_remote {
+                None
+            } else {
+                self.get_upstream_status(&repo, &name).0
+            };
+
+            branches.push(BranchInfo {
+                is_head: !is_remote && head_branch.as_deref() == Some(name.as_str()),
+                has_worktree: !is_remote && worktree_branches.contains(&name),
+                name,
+                is_remote,
+                upstream,
+                ahead,
+                behind,
+            });
+        }
+
+        branches.sort_by(|a, b| a.is_remote.cmp(&b.is_remote).then_with(|| a.name.cmp(&b.name)));
+        Ok(branches)
+    }
+
+    /// Splits a worktree's changes into staged, unstaged and committed-but-unmerged, the
+    /// last relative to `base_branch` (defaults to the default branch).
+    pub fn worktree_change_breakdown(&self, name: &str, base_branch: Option<&str>) -> Result<WorktreeChangeBreakdown> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+
+        let mut status_options = StatusOptions::new();
+        status_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = worktree_repo.statuses(Some(&mut status_options))?;
+
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        for entry in statuses.iter() {
+            let status = entry.status();
+            let file_path = match entry.path() {
+                Some(file_path) => file_path.to_string(),
+                None => continue,
+            };
+
+            let is_staged = status.intersects(
+                git2::Status::INDEX_NEW
+                    | git2::Status::INDEX_MODIFIED
+                    | git2::Status::INDEX_DELETED
+                    | git2::Status::INDEX_RENAMED
+                    | git2::Status::INDEX_TYPECHANGE,
+            );
+            let is_unstaged = status.intersects(
+                git2::Status::WT_NEW
+                    | git2::Status::WT_MODIFIED
+                    | git2::Status::WT_DELETED
+                    | git2::Status::WT_RENAMED
+                    | git2::Status::WT_TYPECHANGE
+                    | git2::Status::CONFLICTED,
+            );
+            // A file staged and then edited again is in both lists
+            if is_staged {
+                staged.push(file_path.clone());
+            }
+            if is_unstaged {
+                unstaged.push(file_path);
+            }
+        }
+
+        let base_branch = match base_branch {
+            Some(base_branch) => Some(base_branch.to_string()),
+            None => self.default_branch().ok(),
+        };
+        let base_oid = base_branch.as_deref().and_then(|branch| Self::branch_oid(&worktree_repo, branch));
+        let head = worktree_repo.head()?.peel_to_commit()?;
+
+        let mut committed = Vec::new();
+        let (mut ahead, mut behind) = (0, 0);
+        if let Some(base_oid) = base_oid {
+            if let Ok(merge_base) = worktree_repo.merge_base(head.id(), base_oid) {
+                (ahead, behind) = worktree_repo.graph_ahead_behind(head.id(), base_oid)?;
+
+                let base_tree = worktree_repo.find_commit(merge_base)?.tree()?;
+                let diff = worktree_repo.diff_tree_to_tree(Some(&base_tree), Some(&head.tree()?), None)?;
+                for delta in diff.deltas() {
+                    let path = delta.new_file().path().or_else(|| delta.old_file().path());
+                    if let Some(path) = path {
+                        committed.push(path.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+
+        staged.sort();
+        unstaged.sort();
+        committed.sort();
+
+        Ok(WorktreeChangeBreakdown {
+            name: name.to_string(),
+            base_branch: base_oid.and(base_branch),
+            staged,
+            unstaged,
+            committed,
+            ahead,
+            behind,
+        })
+    }
+
+    /// Returns up to `limit` commits reachable from the worktree's HEAD, newest first.
+    pub fn get_worktree_log(&self, name: &str, limit: usize) -> Result<Vec<CommitInfo>> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let mut revwalk = worktree_repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut commits = Vec::new();
+        for oid in revwalk.take(limit) {
+            let commit = worktree_repo.find_commit(oid?)?;
+            let author = commit.author();
+            commits.push(CommitInfo {
+                sha: commit.id().to_string(),
+                short_sha: Self::short_sha(&worktree_repo, commit.id()).unwrap_or_else(|| commit.id().to_string()),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: author.name().unwrap_or("unknown").to_string(),
+                time: DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_else(Utc::now),
+            });
+        }
+
+        Ok(commits)
+    }
+
+    /// Diffs a file between the branch tips of two worktrees. A file missing on one
+    /// side is diffed against empty content.
+    pub fn diff_worktrees(&self, name_a: &str, name_b: &str, file_path: &str) -> Result<WorktreeFileDiff> {
+        let content_a = self.read_committed_file(name_a, file_path)?;
+        let content_b = self.read_committed_file(name_b, file_path)?;
+
+        if content_a.is_none() && content_b.is_none() {
+            return Err(anyhow!("File '{}' exists in neither '{}' nor '{}'", file_path, name_a, name_b));
+        }
+
+        let path = Path::new(file_path);
+        let mut patch = Patch::from_buffers(
+            content_a.as_deref().unwrap_or_default(),
+            Some(path),
+            content_b.as_deref().unwrap_or_default(),
+            Some(path),
+            None,
+        )?;
+        let diff = String::from_utf8_lossy(&patch.to_buf()?).to_string();
+
+        Ok(WorktreeFileDiff {
+            file_path: file_path.to_string(),
+            exists_in_a: content_a.is_some(),
+            exists_in_b: content_b.is_some(),
+            diff,
+        })
+    }
+
+    /// Diffs one file in a worktree's working tree against the index or HEAD. Untracked
+    /// files are diffed against empty content. A file with no changes has no hunks.
+    pub fn get_file_diff(&self, name: &str, file_path: &str, base: DiffBase) -> Result<FileDiff> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+
+        let mut diff_options = DiffOptions::new();
+        diff_options
+            .pathspec(file_path)
+            .disable_pathspec_match(true)
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = match base {
+            DiffBase::Index => worktree_repo.diff_index_to_workdir(None, Some(&mut diff_options))?,
+            DiffBase::Head => {
+                // An unborn branch has no HEAD tree; everything is new
+                let head_tree = worktree_repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+                worktree_repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_options))?
+            }
+        };
+
+        let mut file_diff = FileDiff {
+            file_path: file_path.to_string(),
+            base,
+            is_new: false,
+            is_deleted: false,
+            is_binary: false,
+            hunks: Vec::new(),
+            patch: String::new(),
+        };
+
+        let delta = match diff.get_delta(0) {
+            Some(delta) => delta,
+            None => return Ok(file_diff),
+        };
+        file_diff.is_new = matches!(delta.status(), Delta::Added | Delta::Untracked);
+        file_diff.is_deleted = delta.status() == Delta::Deleted;
+
+        let mut patch = match Patch::from_diff(&diff, 0)? {
+            Some(patch) => patch,
+            None => {
+                file_diff.is_binary = true;
+                return Ok(file_diff);
+            }
+        };
+        file_diff.is_binary = delta.flags().is_binary();
+
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_index)?;
+            let mut lines = Vec::with_capacity(line_count);
+            for line_index in 0..line_count {
+                let line = patch.line_in_hunk(hunk_index, line_index)?;
+                let change_type = match line.origin() {
+                    '+' => "added",
+                    '-' => "removed",
+                    ' ' => "context",
+                    _ => continue, // "\ No newline at end of file" markers
+                };
+                lines.push(DiffLine {
+                    change_type: change_type.to_string(),
+                    content: String::from_utf8_lossy(line.content()).trim_end_matches(['\r', '\n']).to_string(),
+                    old_line: line.old_lineno(),
+                    new_line: line.new_lineno(),
+                });
+            }
+
+            file_diff.hunks.push(DiffHunk {
+                header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+        file_diff.patch = String::from_utf8_lossy(&patch.to_buf()?).to_string();
+
+        Ok(file_diff)
+    }
+
+    /// Reads a file as committed at the tip of a worktree's branch, if it exists there.
+    fn read_committed_file(&self, name: &str, file_path: &str) -> Result<Option<Vec<u8>>> {
+        let worktree_repo = self.open_worktree_repo(name)?;
+        let tree = worktree_repo.head()?.peel_to_tree()?;
+
+        let entry = match tree.get_path(Path::new(file_path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let blob = entry.to_object(&worktree_repo)?.peel_to_blob()?;
+
+        Ok(Some(blob.content().to_vec()))
+    }
+
+    /// Returns the repository's default branch: the branch `origin/HEAD` points at,
+    /// falling back to the first of `main`, `master` or `trunk` that exists locally.
+    pub fn default_branch(&self) -> Result<String> {
+        let repo = self.open_repo()?;
+
+        if let Ok(origin_head) = repo.find_reference("refs/remotes/origin/HEAD") {
+            if let Some(target) = origin_head.symbolic_target() {
+                if let Some(branch) = target.strip_prefix("refs/remotes/origin/") {
+                    return Ok(branch.to_string());
+                }
+            }
+        }
+
+        for candidate in ["main", "master", "trunk"] {
+            if repo.find_branch(candidate, BranchType::Local).is_ok() {
+                return Ok(candidate.to_string());
+            }
+        }
+
+        Err(anyhow!("Could not determine the default branch"))
+    }
+
+    /// Resolves a branch name to a commit, preferring the local branch over `origin`'s.
+    fn branch_oid(repo: &Repository, branch: &str) -> Option<git2::Oid> {
+        repo.find_branch(branch, BranchType::Local)
+            .or_else(|_| repo.find_branch(&format!("origin/{}", branch), BranchType::Remote))
+            .ok()
+            .and_then(|branch| branch.get().peel_to_commit().ok())
+            .map(|commit| commit.id())
+    }
+
+    pub fn open_worktree_repo(&self, name: &str) -> Result<Repository> {
+        Ok(Repository::open(self.worktree_path(name)?)?)
+    }
+
+    /// Returns where a worktree's files live, as recorded by git. Worktrees created
+    /// outside the app can be anywhere, not just under `repo_path/worktrees`.
+    pub fn worktree_path(&self, name: &str) -> Result<PathBuf> {
+        let repo = self.open_repo()?;
+        let worktree = repo.find_worktree(name)?;
+        Ok(worktree.path().to_path_buf())
+    }
+
+    fn get_worktree_branch(&self, worktree: &Worktree) -> Result<String> {
+        let worktree_path = worktree.path();
+        let worktree_repo = Repository::open(worktree_path)?;
+        
+        if worktree_repo.head_detached().unwrap_or(false) {
+            return Ok(DETACHED_BRANCH.to_string());
+        }
+
+        if let Ok(head) = worktree_repo.head() {
+            if let Some(branch_name) = head.shorthand() {
+                return Ok(branch_name.to_string());
+            }
+        }
+        
+        Ok("unknown".to_string())
+    }
+
+    fn short_sha(repo: &Repository, oid: git2::Oid) -> Option<String> {
+        let object = repo.find_object(oid, None).ok()?;
+        let short_id = object.short_id().ok()?;
+        short_id.as_str().map(str::to_string)
+    }
+
+    fn get_upstream_status(&self, repo: &Repository, branch: &str) -> (Option<String>, Option<bool>) {
+        let local_branch = match repo.find_branch(branch, BranchType::Local) {
+            Ok(local_branch) => local_branch,
+            Err(_) => return (None, None),
+        };
+        let upstream_branch = match local_branch.upstream() {
+            Ok(upstream_branch) => upstream_branch,
+            Err(_) => return (None, None),
+        };
+
+        let upstream_name = upstream_branch.name().ok().flatten().map(str::to_string);
+        let up_to_date = local_branch.get().target() == upstream_branch.get().target();
+
+        (upstream_name, Some(up_to_date))
+    }
+
+    /// Fetches from `remote_name` using the SSH agent or the configured credential
+    /// helper for authentication. The fetch is aborted if no data arrives for
+    /// `stall_timeout`; a remote that goes completely silent is cut off by the socket
+    /// timeouts from `GitTimeoutConfig::apply_remote_timeouts`. Returns the number of refs
+    /// that were updated.
+    pub fn fetch(&self, remote_name: &str, stall_timeout: Duration) -> Result<usize> {
+        let repo = self.open_repo()?;
+        let mut remote = repo.find_remote(remote_name)?;
+        let config = repo.config()?;
+        let mut updated_refs = 0;
+        let mut stalled = false;
+
+        let result = {
+            let mut tried_ssh_agent = false;
+            let mut tried_credential_helper = false;
+
+            let mut callbacks = RemoteCallbacks::new();
+            callbacks.credentials(|url, username_from_url, allowed_types| {
+                // libgit2 keeps asking until we give up, so only try each method once
+                if allowed_types.contains(CredentialType::SSH_KEY) && !tried_ssh_agent {
+                    tried_ssh_agent = true;
+                    return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+                }
+                if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried_credential_helper {
+                    tried_credential_helper = true;
+                    return Cred::credential_helper(&config, url, username_from_url);
+                }
+                if allowed_types.contains(CredentialType::DEFAULT) {
+                    return Cred::default();
+                }
+                Err(git2::Error::from_str("No usable credentials for remote"))
+            });
+            callbacks.update_tips(|_, _, _| {
+                updated_refs += 1;
+                true
+            });
+
+            // Returning false from the progress callback makes libgit2 abort the transfer
+            let mut last_received = 0;
+            let mut last_progress_at = Instant::now();
+            callbacks.transfer_progress(|progress| {
+                if progress.received_bytes() != last_received {
+                    last_received = progress.received_bytes();
+                    last_progress_at = Instant::now();
+                }
+                stalled = last_progress_at.elapsed() > stall_timeout;
+                !stalled
+            });
+
+            let mut fetch_options = FetchOptions::new();
+            fetch_options.remote_callbacks(callbacks);
+            remote.fetch::<&str>(&[], Some(&mut fetch_options), None)
+        };
+
+        let timed_out = matches!(&result, Err(e) if e.code() == git2::ErrorCode::Timeout);
+        if stalled || timed_out {
+            return Err(WhiplashError::Timeout(format!(
+                "Fetch from '{}' stalled for {} seconds",
+                remote_name,
+                stall_timeout.as_secs()
+            )).into());
+        }
+        result?;
+
+        Ok(updated_refs)
+    }
+
+    fn get_ahead_behind(&self, repo: &Repository, worktree: &Worktree, base_oid: git2::Oid) -> (usize, usize, bool) {
+        let head_oid = match Repository::open(worktree.path()) {
+            Ok(worktree_repo) => worktree_repo.head().ok().and_then(|head| head.target()),
+            Err(_) => None,
+        };
+
+        match head_oid {
+            Some(head_oid) if repo.merge_base(head_oid, base_oid).is_ok() => {
+                match repo.graph_ahead_behind(head_oid, base_oid) {
+                    Ok((ahead, behind)) => (ahead, behind, true),
+                    Err(_) => (0, 0, true),
+                }
+            }
+            _ => (0, 0, false),
+        }
+    }
+
+    /// The latest of the branch's newest commit and the newest mtime among the worktree's
+    /// uncommitted files, i.e. when someone last actually touched it.
+    fn get_worktree_last_activity(&self, worktree: &Worktree) -> Option<DateTime<Utc>> {
+        let worktree_repo = Repository::open(worktree.path()).ok()?;
+
+        let commit_time = worktree_repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .and_then(|commit| DateTime::from_timestamp(commit.time().seconds(), 0));
+
+        // Each dirty file is stat'ed once; deleted files have no mtime and are skipped
+        let mut status_options = StatusOptions::new();
+        status_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let newest_file_change = worktree_repo
+            .statuses(Some(&mut status_options))
+            .ok()
+            .and_then(|statuses| {
+                statuses.iter()
+                    .filter_map(|entry| entry.path().map(|path| worktree.path().join(path)))
+                    .filter_map(|path| Self::path_modified_time(&path))
+                    .max()
+            });
+
+        commit_time.into_iter().chain(newest_file_change).max()
+    }
+
+    fn path_modified_time(path: &Path) -> Option<DateTime<Utc>> {
+        let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+        Some(modified.into())
+    }
+
+    /// Reads the persisted metadata for a worktree, minting and persisting a new
+    /// id for worktrees that were created outside the app.
+    fn load_or_init_metadata(&self, name: &str, worktree: &Worktree) -> Result<WorktreeMetadata> {
+        if let Some(metadata) = self.read_metadata(name) {
+            return Ok(metadata);
+        }
+
+        let metadata = WorktreeMetadata {
+            id: Uuid::new_v4().to_string(),
+            created_at: Self::path_modified_time(worktree.path()).unwrap_or_else(Utc::now),
+            base_dir: None,
+        };
+        self.write_metadata(name, &metadata)?;
+
+        Ok(metadata)
+    }
+
+    fn metadata_path(&self, name: &str) -> PathBuf {
+        self.worktrees_dir().join(".whiplash").join(format!("{}.json", name))
+    }
+
+    fn read_metadata(&self, name: &str) -> Option<WorktreeMetadata> {
+        let content = std::fs::read_to_string(self.metadata_path(name)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_metadata(&self, name: &str, metadata: &WorktreeMetadata) -> Result<()> {
+        let metadata_path = self.metadata_path(name);
+        if let Some(parent) = metadata_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(metadata_path, serde_json::to_string_pretty(metadata)?)?;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_worktree(
+    app: AppHandle,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    branch: String,
+    base: Option<String>,
+    create_branch: Option<bool>,
+    base_dir: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<GitWorktreeInfo, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let manager = GitWorktreeManager::new(repo_path)?;
+
+        // Checkout reports every file, so only emit when the percentage moves
+        let mut last_percent = None;
+        let mut report_progress = |completed: usize, total: usize| {
+            let percent = (completed * 100).checked_div(total).unwrap_or(100);
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit("worktree-create-progress", WorktreeCreateProgress {
+                    name: name.clone(),
+                    completed,
+                    total,
+                });
+            }
+        };
+
+        manager.create_worktree(
+            &name,
+            &branch,
+            base.as_deref(),
+            create_branch.unwrap_or(true),
+            base_dir.as_deref().map(Path::new),
+            dry_run.unwrap_or(false),
+            Some(&mut report_progress),
+        )
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn fork_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    source_name: String,
+    new_name: String,
+    new_branch: String,
+) -> Result<GitWorktreeInfo, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.fork_worktree(&source_name, &new_name, &new_branch)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_worktrees(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    base_branch: Option<String>,
+    include_disk_usage: Option<bool>,
+) -> Result<Vec<GitWorktreeInfo>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let manager = GitWorktreeManager::new(repo_path)?;
+        let mut worktrees = manager.list_worktrees(base_branch.as_deref())?;
+
+        // Walking every worktree is slow, so it's opt-in
+        if include_disk_usage.unwrap_or(false) {
+            for worktree in &mut worktrees {
+                worktree.disk_usage_bytes = manager.worktree_disk_usage(&worktree.name).ok();
+            }
+        }
+
+        Ok(worktrees)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_branches(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    include_remote: Option<bool>,
+) -> Result<Vec<BranchInfo>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.list_branches(include_remote.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn worktree_disk_usage(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<u64, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.worktree_disk_usage(&name)).await
+}
+
+#[tauri::command]
+pub async fn delete_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    force: Option<bool>,
+    delete_branch: Option<bool>,
+) -> Result<WorktreeDeletion, WhiplashError> {
+    // Removing only the worktree doesn't touch shared refs
+    let guard = match delete_branch {
+        Some(true) => Some(repo_locks.lock(&repo_path).await),
+        _ => None,
+    };
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?
+            .delete_worktree(&name, force.unwrap_or(false), delete_branch.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn merge_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    signature_config: State<'_, SignatureConfig>,
+    repo_path: String,
+    name: String,
+    target_branch: Option<String>,
+    strategy: Option<MergeStrategy>,
+) -> Result<MergeResult, WhiplashError> {
+    // The guard moves into the blocking work, so the repo stays locked until git is
+    // done even if the command times out first
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let default_signature = signature_config.default_signature.clone();
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        let manager = GitWorktreeManager::new(repo_path)?.with_default_signature(default_signature);
+        let target_branch = match target_branch {
+            Some(target_branch) => target_branch,
+            None => manager.default_branch()?,
+        };
+        manager.merge_worktree(&name, &target_branch, strategy.unwrap_or_default())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rebase_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    signature_config: State<'_, SignatureConfig>,
+    repo_path: String,
+    name: String,
+    onto_branch: Option<String>,
+) -> Result<RebaseResult, WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let default_signature = signature_config.default_signature.clone();
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        let manager = GitWorktreeManager::new(repo_path)?.with_default_signature(default_signature);
+        let onto_branch = match onto_branch {
+            Some(onto_branch) => onto_branch,
+            None => manager.default_branch()?,
+        };
+        manager.rebase_worktree(&name, &onto_branch)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn continue_rebase_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    signature_config: State<'_, SignatureConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<RebaseResult, WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let default_signature = signature_config.default_signature.clone();
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?
+            .with_default_signature(default_signature)
+            .continue_rebase(&name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn abort_rebase_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<(), WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?.abort_rebase(&name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn abort_git_operation(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: Option<String>,
+) -> Result<AbortedOperation, WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?.abort_git_operation(name.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn stash_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    signature_config: State<'_, SignatureConfig>,
+    repo_path: String,
+    name: String,
+    message: Option<String>,
+) -> Result<String, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let default_signature = signature_config.default_signature.clone();
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?
+            .with_default_signature(default_signature)
+            .stash_worktree(&name, message.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn stash_pop_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    stash_id: Option<String>,
+) -> Result<String, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.stash_pop_worktree(&name, stash_id.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_worktree_stashes(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<Vec<StashInfo>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.list_worktree_stashes(&name)).await
+}
+
+#[tauri::command]
+pub async fn commit_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    signature_config: State<'_, SignatureConfig>,
+    repo_path: String,
+    name: String,
+    message: String,
+    stage_all: Option<bool>,
+    signature: Option<CommitSignature>,
+) -> Result<String, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let default_signature = signature_config.default_signature.clone();
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?
+            .with_default_signature(default_signature)
+            .commit_worktree(&name, &message, stage_all.unwrap_or(false), signature.as_ref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_worktree_summaries(
+    runner: State<'_, ClaudeRunner>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+) -> Result<Vec<WorktreeSummary>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let mut summaries =
+        run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.get_worktree_summaries()).await?;
+
+    let task_counts = runner.active_task_counts().await;
+    for summary in &mut summaries {
+        summary.running_tasks = task_counts.get(&summary.name).copied().unwrap_or(0);
+    }
+
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn get_worktree_log(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    limit: Option<usize>,
+) -> Result<Vec<CommitInfo>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.get_worktree_log(&name, limit.unwrap_or(20))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn diff_worktrees(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name_a: String,
+    name_b: String,
+    file_path: String,
+) -> Result<WorktreeFileDiff, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.diff_worktrees(&name_a, &name_b, &file_path)
+    })
+    .await
+}
 
 #[tauri::command]
-pub async fn get_modified_files(repo_path: String, name: String) -> Result<Vec<String>, String> {
-    let manager = GitWorktreeManager::new(repo_path).map_err(|e| e.to_string())?;
-    manager.get_modified_files(&name).map_err(|e| e.to_string())
+pub async fn get_file_diff(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    worktree_name: String,
+    file_path: String,
+    base: Option<DiffBase>,
+) -> Result<FileDiff, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.get_file_diff(&worktree_name, &file_path, base.unwrap_or_default())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn fetch_remote(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    remote: String,
+) -> Result<usize, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let stall_timeout = Duration::from_secs(timeouts.fetch_stall_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.fetch(&remote, stall_timeout)).await
+}
+
+#[tauri::command]
+pub async fn rename_worktree(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    old_name: String,
+    new_name: String,
+    new_branch: Option<String>,
+) -> Result<GitWorktreeInfo, WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?.rename_worktree(&old_name, &new_name, new_branch.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn lock_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    reason: String,
+) -> Result<(), WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.lock_worktree(&name, &reason)).await
+}
+
+#[tauri::command]
+pub async fn unlock_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<(), WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.unlock_worktree(&name)).await
+}
+
+#[tauri::command]
+pub async fn archive_worktree(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    dest_path: String,
+    delete_after: Option<bool>,
+) -> Result<WorktreeArchive, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?
+            .archive_worktree(&name, Path::new(&dest_path), delete_after.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn prune_worktrees(
+    repo_locks: State<'_, RepoLocks>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+) -> Result<Vec<String>, WhiplashError> {
+    let guard = repo_locks.lock(&repo_path).await;
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let _guard = guard;
+        GitWorktreeManager::new(repo_path)?.prune_worktrees()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_worktree_status(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<Vec<FileStatus>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.get_worktree_status(&name)).await
+}
+
+#[tauri::command]
+pub async fn worktree_change_breakdown(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    base_branch: Option<String>,
+) -> Result<WorktreeChangeBreakdown, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        GitWorktreeManager::new(repo_path)?.worktree_change_breakdown(&name, base_branch.as_deref())
+    })
+    .await
+}
+
+/// The old `"<states>: <path>"` string form of `get_worktree_status`.
+#[tauri::command]
+pub async fn get_worktree_status_lines(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+) -> Result<Vec<String>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let statuses = run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.get_worktree_status(&name)).await?;
+    Ok(statuses.into_iter()
+        .map(|status| format!("{}: {}", status.states.join(", "), status.path))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_modified_files(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    include_untracked: Option<bool>,
+    categories: Option<ChangeCategories>,
+) -> Result<Vec<String>, WhiplashError> {
+    let mut categories = categories.unwrap_or_default();
+    if include_untracked == Some(false) {
+        categories = categories.without(ChangeCategories::UNTRACKED);
+    }
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.get_modified_files(&name, categories)).await
+}
+
+#[tauri::command]
+pub async fn check_worktree_conflicts(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    name: String,
+    target_branch: Option<String>,
+) -> Result<Vec<String>, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        let manager = GitWorktreeManager::new(repo_path)?;
+        let target_branch = match target_branch {
+            Some(target_branch) => target_branch,
+            None => manager.default_branch()?,
+        };
+        manager.check_merge_conflicts(&name, &target_branch)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_default_branch(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+) -> Result<String, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || GitWorktreeManager::new(repo_path)?.default_branch()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture_repo;
+
+    fn error_kind<T: std::fmt::Debug>(result: Result<T>) -> WhiplashError {
+        result.unwrap_err().downcast::<WhiplashError>().unwrap()
+    }
+
+    #[test]
+    fn worktree_names_reject_traversal_and_empty_names() {
+        for name in ["..", "", "../escape", "nested/name"] {
+            assert!(
+                matches!(error_kind(GitWorktreeManager::validate_worktree_name(name)), WhiplashError::InvalidInput(_)),
+                "'{}' should be rejected",
+                name
+            );
+        }
+        assert!(GitWorktreeManager::validate_worktree_name("feature-1.2_b").is_ok());
+
+        for branch in ["", "..", "feature..x", "has space", "ends.lock"] {
+            assert!(GitWorktreeManager::validate_branch_name(branch).is_err(), "'{}' should be rejected", branch);
+        }
+        assert!(GitWorktreeManager::validate_branch_name("feature/login").is_ok());
+    }
+
+    #[test]
+    fn creating_a_worktree_with_an_existing_name_fails() {
+        let (dir, _repo) = fixture_repo(&[("README.md", "hello\n")]);
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+        manager.create_worktree("feature", "feature", None, true, None, false, None).unwrap();
+
+        let duplicate = manager.create_worktree("feature", "other", None, true, None, false, None);
+        assert!(matches!(error_kind(duplicate), WhiplashError::AlreadyExists(_)));
+        let traversal = manager.create_worktree("..", "other", None, true, None, false, None);
+        assert!(matches!(error_kind(traversal), WhiplashError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn worktree_ids_are_stable_across_listings() {
+        let (dir, _repo) = fixture_repo(&[("README.md", "hello\n")]);
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+        let created = manager.create_worktree("feature", "feature", None, true, None, false, None).unwrap();
+
+        let listed_id = || {
+            manager.list_worktrees(None).unwrap()
+                .into_iter()
+                .find(|worktree| worktree.name == "feature")
+                .unwrap()
+                .id
+        };
+        assert_eq!(listed_id(), created.id);
+        assert_eq!(listed_id(), created.id);
+    }
+
+    #[test]
+    fn creating_a_worktree_reports_checkout_progress() {
+        let (dir, repo) = fixture_repo(&[("README.md", "hello\n"), ("src/lib.rs", "fn main() {}\n")]);
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+
+        let mut reports = Vec::new();
+        let mut progress = |completed: usize, total: usize| reports.push((completed, total));
+        let created = manager
+            .create_worktree("feature", "feature", None, true, None, false, Some(&mut progress))
+            .unwrap();
+
+        assert_eq!(reports.last(), Some(&(2, 2)));
+        assert!(Path::new(&created.path).join("src/lib.rs").exists());
+        assert!(manager.dirty_files("feature").unwrap().is_empty());
+        let branches: Vec<String> = repo.branches(Some(BranchType::Local)).unwrap()
+            .map(|branch| branch.unwrap().0.name().unwrap().unwrap().to_string())
+            .collect();
+        assert!(!branches.iter().any(|branch| branch.starts_with("whiplash-checkout-")), "{:?}", branches);
+    }
+
+    #[test]
+    fn a_failed_worktree_add_deletes_the_branch_it_created() {
+        let (dir, repo) = fixture_repo(&[("README.md", "hello\n")]);
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+        // A file where the worktree should go makes libgit2 refuse to add it
+        std::fs::create_dir_all(manager.worktrees_dir()).unwrap();
+        std::fs::write(manager.worktrees_dir().join("feature"), "in the way").unwrap();
+
+        let mut progress = |_: usize, _: usize| {};
+        assert!(manager.create_worktree("feature", "feature", None, true, None, false, Some(&mut progress)).is_err());
+        assert!(manager.create_worktree("feature", "feature", None, true, None, false, None).is_err());
+        assert!(repo.find_branch("feature", BranchType::Local).is_err());
+        assert!(repo.find_worktree("feature").is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Extracts every dependency referenced by a source file, picking a parser based on
+/// the file extension. Dependencies are returned in order of appearance, deduplicated.
+pub fn extract_dependencies(file_path: &Path, content: &str) -> Vec<String> {
+    let extension = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("");
+
+    let dependencies = match extension {
+        "rs" => extract_rust_dependencies(content),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => extract_js_dependencies(content),
+        "py" => extract_python_dependencies(content),
+        _ => Vec::new(),
+    };
+
+    let mut unique = Vec::new();
+    for dependency in dependencies {
+        if !unique.contains(&dependency) {
+            unique.push(dependency);
+        }
+    }
+    unique
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("invalid import regex"))
+}
+
+/// Rust `use` trees are expanded into full paths, e.g. `use crate::a::{b, c::d}` yields
+/// `crate::a::b` and `crate::a::c::d`. `mod foo;` declarations yield `foo`.
+fn extract_rust_dependencies(content: &str) -> Vec<String> {
+    static USE_RE: OnceLock<Regex> = OnceLock::new();
+    static MOD_RE: OnceLock<Regex> = OnceLock::new();
+    let use_re = regex(&USE_RE, r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+([^;]+);");
+    let mod_re = regex(&MOD_RE, r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;");
+
+    let mut dependencies = Vec::new();
+    for captures in use_re.captures_iter(content) {
+        expand_use_tree("", &captures[1], &mut dependencies);
+    }
+    for captures in mod_re.captures_iter(content) {
+        dependencies.push(captures[1].to_string());
+    }
+    dependencies
+}
+
+fn expand_use_tree(prefix: &str, tree: &str, dependencies: &mut Vec<String>) {
+    let tree = tree.trim();
+
+    if let (Some(open), Some(close)) = (tree.find('{'), tree.rfind('}')) {
+        let head = tree[..open].trim().trim_end_matches("::");
+        let prefix = join_rust_path(prefix, head);
+        for part in split_top_level(&tree[open + 1..close]) {
+            expand_use_tree(&prefix, part, dependencies);
+        }
+        return;
+    }
+
+    let path = tree.split(" as ").next().unwrap_or("").trim();
+    match path {
+        "" => {}
+        "self" => dependencies.push(prefix.to_string()),
+        _ => dependencies.push(join_rust_path(prefix, path)),
+    }
+}
+
+fn join_rust_path(prefix: &str, path: &str) -> String {
+    match (prefix.is_empty(), path.is_empty()) {
+        (true, _) => path.to_string(),
+        (_, true) => prefix.to_string(),
+        _ => format!("{}::{}", prefix, path),
+    }
+}
+
+/// Splits a use-tree group on commas that aren't nested inside braces.
+fn split_top_level(group: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, ch) in group.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&group[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&group[start..]);
+
+    parts
+}
+
+/// Handles ES module `import`/`export ... from`, side-effect imports, dynamic
+/// `import()` and CommonJS `require()`.
+fn extract_js_dependencies(content: &str) -> Vec<String> {
+    static IMPORT_RE: OnceLock<Regex> = OnceLock::new();
+    static EXPORT_RE: OnceLock<Regex> = OnceLock::new();
+    static CALL_RE: OnceLock<Regex> = OnceLock::new();
+    let import_re = regex(&IMPORT_RE, r#"\bimport\s+(?:[^'";]*?\s+from\s+)?['"]([^'"]+)['"]"#);
+    let export_re = regex(&EXPORT_RE, r#"\bexport\s+[^'";]*?\s+from\s+['"]([^'"]+)['"]"#);
+    let call_re = regex(&CALL_RE, r#"\b(?:require|import)\s*\(\s*['"]([^'"]+)['"]\s*\)"#);
+
+    let mut matches: Vec<(usize, String)> = Vec::new();
+    for re in [import_re, export_re, call_re] {
+        for captures in re.captures_iter(content) {
+            let dependency = captures.get(1).expect("import regex has a capture group");
+            matches.push((dependency.start(), dependency.as_str().to_string()));
+        }
+    }
+
+    matches.sort_by_key(|(position, _)| *position);
+    matches.into_iter().map(|(_, dependency)| dependency).collect()
+}
+
+/// Handles `import a, b as c` and `from x import y`, returning module names.
+fn extract_python_dependencies(content: &str) -> Vec<String> {
+    static IMPORT_RE: OnceLock<Regex> = OnceLock::new();
+    static FROM_RE: OnceLock<Regex> = OnceLock::new();
+    let import_re = regex(&IMPORT_RE, r"(?m)^\s*import\s+([^#\n]+)");
+    let from_re = regex(&FROM_RE, r"(?m)^\s*from\s+(\S+)\s+import\b");
+
+    let mut matches: Vec<(usize, String)> = Vec::new();
+    for captures in import_re.captures_iter(content) {
+        let modules = captures.get(1).expect("import regex has a capture group");
+        for module in modules.as_str().split(',') {
+            let module = module.split(" as ").next().unwrap_or("").trim();
+            if !module.is_empty() {
+                matches.push((modules.start(), module.to_string()));
+            }
+        }
+    }
+    for captures in from_re.captures_iter(content) {
+        let module = captures.get(1).expect("from regex has a capture group");
+        matches.push((module.start(), module.as_str().to_string()));
+    }
+
+    matches.sort_by_key(|(position, _)| *position);
+    matches.into_iter().map(|(_, dependency)| dependency).collect()
+}
@@ -1,10 +1,21 @@
 mod git_worktree;
 mod claude_runner;
 mod overlap_analyzer;
+mod error;
+mod import_parser;
+mod symbol_parser;
+mod worktree_watcher;
+mod worktree_opener;
+#[cfg(test)]
+mod test_support;
 
 use git_worktree::*;
 use claude_runner::*;
 use overlap_analyzer::*;
+use worktree_watcher::*;
+use worktree_opener::*;
+use std::time::Duration;
+use tauri::{Manager, RunEvent};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -14,26 +25,96 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let git_timeouts = GitTimeoutConfig::default();
+    // libgit2's timeouts are globals, so they're set before tauri starts any threads
+    if let Err(e) = git_timeouts.apply_remote_timeouts() {
+        eprintln!("Failed to set git remote timeouts: {}", e);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            let store_path = app.path().app_data_dir()?.join("claude_tasks.json");
+            let config_path = app.path().app_config_dir()?.join("runner_config.json");
+            let runner: ClaudeRunner = ClaudeRunner::new(ClaudeRunnerConfig::load(&config_path))
+                .with_config_path(config_path)
+                .with_store(store_path);
+            app.manage(runner);
+            Ok(())
+        })
+        .manage(OverlapCache::default())
+        .manage(RepoLocks::default())
+        .manage(git_timeouts)
+        .manage(SignatureConfig::default())
+        .manage(OpenerConfig::default())
+        .manage(WorktreeWatcher::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             // Git worktree commands
             create_worktree,
+            fork_worktree,
             list_worktrees,
+            get_worktree_summaries,
+            list_branches,
+            worktree_disk_usage,
             delete_worktree,
+            rename_worktree,
+            prune_worktrees,
+            archive_worktree,
+            lock_worktree,
+            unlock_worktree,
             get_worktree_status,
+            get_worktree_status_lines,
+            worktree_change_breakdown,
             get_modified_files,
+            check_worktree_conflicts,
+            merge_worktree,
+            rebase_worktree,
+            continue_rebase_worktree,
+            abort_rebase_worktree,
+            abort_git_operation,
+            diff_worktrees,
+            get_file_diff,
+            commit_worktree,
+            get_worktree_log,
+            stash_worktree,
+            stash_pop_worktree,
+            list_worktree_stashes,
+            fetch_remote,
+            get_default_branch,
+            start_worktree_watcher,
+            stop_worktree_watcher,
+            open_worktree_in,
             // Claude runner commands
+            check_claude_available,
             start_claude_task,
+            start_claude_task_chain,
+            cancel_claude_task_chain,
+            retry_claude_task,
             get_claude_task_status,
+            get_claude_task_output_since,
+            get_claude_task_log_path,
             list_claude_tasks,
+            get_runner_config,
+            update_runner_config,
+            pause_scheduler,
+            resume_scheduler,
+            send_claude_task_input,
             cancel_claude_task,
+            cancel_all_claude_tasks,
             cleanup_completed_claude_tasks,
             // Overlap analyzer commands
             analyze_worktree_overlaps,
             analyze_file_dependencies,
+            preview_merge,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Kill any claude processes still running so they don't outlive the app
+            if let RunEvent::ExitRequested { .. } = event {
+                let runner = app.state::<ClaudeRunner>().inner().clone();
+                tauri::async_runtime::block_on(runner.shutdown(Duration::from_secs(5)));
+            }
+        });
 }
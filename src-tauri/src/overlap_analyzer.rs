@@ -1,9 +1,20 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 use std::path::{Path, PathBuf};
-use anyhow::Result;
+use std::time::Duration;
+use crate::error::WhiplashError;
+use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
-use crate::git_worktree::GitWorktreeManager;
+use git2::{Delta, DiffOptions, IndexEntry, IndexTime, MergeFileOptions, Oid, Patch, Repository, StatusOptions};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tauri::State;
+use rayon::prelude::*;
+use crate::git_worktree::{run_blocking, ChangeCategories, GitTimeoutConfig, GitWorktreeManager};
+use crate::import_parser;
+use crate::symbol_parser;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileOverlapInfo {
@@ -12,6 +23,16 @@ pub struct FileOverlapInfo {
     pub conflict_risk: String,
     pub last_modified: HashMap<String, DateTime<Utc>>,
     pub line_changes: HashMap<String, LineChangeInfo>,
+    /// Functions, types and modules changed in more than one worktree. Only filled in for
+    /// languages `symbol_parser` understands.
+    pub symbol_overlaps: Vec<String>,
+    /// Commit shared by every worktree involved; line changes are measured against it.
+    /// `None` when the worktrees have no common history.
+    pub merge_base: Option<String>,
+    /// Every worktree ended up with the same content, so merging them is trivial.
+    pub identical_changes: bool,
+    /// Every pair of worktrees' versions three-way merges without conflicts.
+    pub auto_mergeable: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,13 +41,17 @@ pub struct LineChangeInfo {
     pub lines_removed: usize,
     pub lines_modified: usize,
     pub change_regions: Vec<ChangeRegion>,
+    /// Binary files have no line information; they contribute a fixed risk weight instead.
+    pub is_binary: bool,
+    /// The worktree deleted the file. Overlaps involving a deletion are always high risk.
+    pub is_deleted: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChangeRegion {
     pub start_line: usize,
     pub end_line: usize,
-    pub change_type: String, // "added", "removed", "modified"
+    pub change_type: String, // "added", "removed", "modified", "binary"
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,102 +59,520 @@ pub struct OverlapAnalysisResult {
     pub total_overlaps: usize,
     pub file_overlaps: Vec<FileOverlapInfo>,
     pub risk_assessment: RiskAssessment,
-    pub recommendations: Vec<String>,
+    pub recommendations: Vec<Recommendation>,
+    /// Number of overlapping files each worktree is part of.
+    pub worktree_involvement: HashMap<String, usize>,
+    /// The worktree involved in the most overlaps, ties broken by name.
+    pub hottest_worktree: Option<String>,
+    /// Changed files left out because they match `.whiplashignore`.
+    pub excluded_files: usize,
+    /// Risk counts per file extension (without the dot); files with no extension are
+    /// grouped under `"(none)"`.
+    pub by_extension: HashMap<String, RiskAssessment>,
 }
 
+/// A suggestion drawn from the overlaps. `code` identifies the kind of suggestion so
+/// the frontend can localize it; `message` is an English fallback.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub severity: RecommendationSeverity,
+    pub code: RecommendationCode,
+    pub message: String,
+    /// Files the recommendation is about, for linking to them.
+    pub related_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationCode {
+    NoOverlaps,
+    HighRiskFiles,
+    MediumRiskFiles,
+    AutoMergeable,
+    MostProblematicFile,
+    SplitBranches,
+    DesignateOwnership,
+}
+
+impl Recommendation {
+    fn new(severity: RecommendationSeverity, code: RecommendationCode, message: String, related_files: Vec<String>) -> Self {
+        Self { severity, code, message, related_files }
+    }
+
+    /// The message with an emoji marking its kind, as recommendations used to be returned.
+    pub fn render(&self) -> String {
+        let icon = match self.code {
+            RecommendationCode::NoOverlaps => return self.message.clone(),
+            RecommendationCode::HighRiskFiles => "⚠️",
+            RecommendationCode::MediumRiskFiles => "⚡",
+            RecommendationCode::AutoMergeable => "✅",
+            RecommendationCode::MostProblematicFile => "🔥",
+            RecommendationCode::SplitBranches => "💡",
+            RecommendationCode::DesignateOwnership => "🤝",
+        };
+        format!("{} {}", icon, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RiskAssessment {
     pub low: usize,
     pub medium: usize,
     pub high: usize,
 }
 
+impl RiskAssessment {
+    fn record(&mut self, conflict_risk: &str) {
+        match conflict_risk {
+            "low" => self.low += 1,
+            "medium" => self.medium += 1,
+            "high" => self.high += 1,
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DependencyInfo {
+    /// Repo-relative path of the analyzed file.
     pub file_path: String,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    /// Repo-relative paths of the analyzed files that import this one.
     pub dependents: Vec<String>,
     pub impact_score: f64,
 }
 
+/// Result of `analyze_dependencies`. Files that couldn't be analyzed are listed in
+/// `skipped` rather than failing the whole batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyAnalysis {
+    pub files: Vec<DependencyInfo>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub file_path: String,
+    pub reason: String,
+}
+
+/// One import of a file, e.g. `./components/Header` or `crate::git_worktree`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    /// The import as written in the source.
+    pub raw: String,
+    /// Repo-relative path of the imported file, when it could be found in the repo.
+    /// Package and standard library imports stay unresolved.
+    pub resolved_path: Option<String>,
+}
+
+/// Result of merging one file from two worktrees, as `preview_merge` would produce it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergePreview {
+    pub file_path: String,
+    /// Merged content, with diff3-style conflict markers around conflicting regions.
+    pub content: String,
+    pub has_conflicts: bool,
+    /// False when the worktrees share no history, in which case the merge was done
+    /// against an empty ancestor and every differing region conflicts.
+    pub has_common_ancestor: bool,
+    pub conflict_regions: Vec<ConflictRegion>,
+}
+
+/// Lines (1-based, inclusive) of a conflict block in `MergePreview::content`, markers included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictRegion {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Controls which changed files take part in overlap analysis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlapFilterConfig {
+    pub include_untracked: bool,
+    /// Kinds of change that count as modifying a file. Untracked files additionally
+    /// need `include_untracked`.
+    pub change_categories: ChangeCategories,
+    /// Glob patterns for files to leave out, e.g. `["*.lock", "dist/**"]`.
+    pub ignore_patterns: Vec<String>,
+    /// Only compare these worktrees. All worktrees are compared when unset.
+    pub worktree_names: Option<Vec<String>>,
+}
+
+impl Default for OverlapFilterConfig {
+    fn default() -> Self {
+        Self {
+            include_untracked: true,
+            change_categories: ChangeCategories::default(),
+            ignore_patterns: Vec::new(),
+            worktree_names: None,
+        }
+    }
+}
+
+/// Weights and thresholds used by `assess_conflict_risk`. A file's risk is the sum of
+/// its extension weight, a change-count weight and a worktree-count weight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Base risk per file extension (without the dot).
+    pub extension_weights: BTreeMap<String, usize>,
+    /// Base risk for extensions not listed in `extension_weights`.
+    pub default_extension_weight: usize,
+    /// More changed lines than this adds 1 to the risk.
+    pub medium_change_threshold: usize,
+    /// More changed lines than this adds 2 to the risk.
+    pub high_change_threshold: usize,
+    /// More worktrees touching the file than this adds 1 to the risk.
+    pub medium_worktree_threshold: usize,
+    /// More worktrees touching the file than this adds 2 to the risk.
+    pub high_worktree_threshold: usize,
+    /// Highest total risk still reported as "low".
+    pub low_cutoff: usize,
+    /// Highest total risk still reported as "medium".
+    pub medium_cutoff: usize,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        let mut extension_weights = BTreeMap::new();
+        // Code files have higher risk
+        for ext in ["rs", "ts", "js", "py", "go", "java"] {
+            extension_weights.insert(ext.to_string(), 2);
+        }
+        // Config files have highest risk
+        for ext in ["json", "yaml", "toml", "xml"] {
+            extension_weights.insert(ext.to_string(), 3);
+        }
+        // Documentation has lower risk
+        for ext in ["md", "txt"] {
+            extension_weights.insert(ext.to_string(), 1);
+        }
+
+        Self {
+            extension_weights,
+            default_extension_weight: 2,
+            medium_change_threshold: 50,
+            high_change_threshold: 100,
+            medium_worktree_threshold: 2,
+            high_worktree_threshold: 3,
+            low_cutoff: 3,
+            medium_cutoff: 6,
+        }
+    }
+}
+
+/// Number of changed lines a modified binary file counts as when assessing risk.
+const BINARY_CHANGE_WEIGHT: usize = 50;
+
+/// Files larger than this are skipped by dependency analysis unless overridden, since
+/// they're usually generated and would be read into memory whole.
+const DEFAULT_MAX_DEPENDENCY_FILE_BYTES: u64 = 1024 * 1024;
+
+const RESOLVABLE_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "rs", "py"];
+
+/// Name of the repo-root file listing paths the analyzers skip, in gitignore syntax.
+const IGNORE_FILE_NAME: &str = ".whiplashignore";
+
+/// Patterns from `.whiplashignore`. Supports the common subset of gitignore syntax:
+/// comments, `!` negation, a leading `/` to anchor to the root and a trailing `/` for
+/// directories. A negated pattern re-includes a path regardless of where it appears.
+#[derive(Default)]
+struct IgnoreFile {
+    lines: Vec<String>,
+    ignore: GlobSet,
+    keep: GlobSet,
+}
+
+impl IgnoreFile {
+    fn load(repo_path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(repo_path.join(IGNORE_FILE_NAME)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut ignore = GlobSetBuilder::new();
+        let mut keep = GlobSetBuilder::new();
+        let mut lines = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            lines.push(line.to_string());
+
+            let (builder, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (&mut keep, pattern),
+                None => (&mut ignore, line),
+            };
+            // Like gitignore, a pattern with a slash other than a trailing one is relative
+            // to the root; otherwise it matches at any depth
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/');
+            let pattern = if anchored { pattern.to_string() } else { format!("**/{}", pattern) };
+
+            let invalid = |e: globset::Error| anyhow!("Invalid pattern '{}' in {}: {}", line, IGNORE_FILE_NAME, e);
+            // Matching a directory excludes everything under it
+            builder.add(Glob::new(&pattern).map_err(invalid)?);
+            builder.add(Glob::new(&format!("{}/**", pattern)).map_err(invalid)?);
+        }
+
+        Ok(Self {
+            lines,
+            ignore: ignore.build()?,
+            keep: keep.build()?,
+        })
+    }
+
+    fn is_ignored(&self, file_path: &str) -> bool {
+        self.ignore.is_match(file_path) && !self.keep.is_match(file_path)
+    }
+}
+
+/// Resolved imports keyed by the importer context and the import as written, shared by
+/// the threads of one `analyze_dependencies` call.
+type ResolutionCache = Mutex<HashMap<(PathBuf, String), Option<String>>>;
+
+/// Last analysis result per repo, keyed by a fingerprint of every worktree's state.
+/// Lives in Tauri managed state since analyzers are created per command. Clones share
+/// the same entries, so a command can hand the cache to a blocking task.
+#[derive(Default, Clone)]
+pub struct OverlapCache {
+    entries: Arc<Mutex<HashMap<PathBuf, (u64, OverlapAnalysisResult)>>>,
+}
+
 pub struct OverlapAnalyzer {
     repo_path: PathBuf,
     git_manager: GitWorktreeManager,
+    filter: OverlapFilterConfig,
+    ignore_set: GlobSet,
+    ignore_file: IgnoreFile,
+    risk_config: RiskConfig,
+    max_dependency_file_bytes: u64,
 }
 
 impl OverlapAnalyzer {
     pub fn new(repo_path: impl AsRef<Path>) -> Result<Self> {
         let repo_path = repo_path.as_ref().to_path_buf();
         let git_manager = GitWorktreeManager::new(&repo_path)?;
+        let ignore_file = IgnoreFile::load(&repo_path)?;
         
         Ok(Self {
             repo_path,
             git_manager,
+            filter: OverlapFilterConfig::default(),
+            ignore_set: GlobSet::empty(),
+            ignore_file,
+            risk_config: RiskConfig::default(),
+            max_dependency_file_bytes: DEFAULT_MAX_DEPENDENCY_FILE_BYTES,
         })
     }
 
+    pub fn with_risk_config(mut self, risk_config: RiskConfig) -> Self {
+        self.risk_config = risk_config;
+        self
+    }
+
+    pub fn with_max_dependency_file_size(mut self, max_bytes: u64) -> Self {
+        self.max_dependency_file_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: OverlapFilterConfig) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &filter.ignore_patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+
+        self.ignore_set = builder.build()?;
+        self.filter = filter;
+        Ok(self)
+    }
+
     pub fn analyze_overlaps(&self) -> Result<OverlapAnalysisResult> {
-        let worktrees = self.git_manager.list_worktrees()?;
+        let worktrees: Vec<_> = self.git_manager.list_worktrees(None)?
+            .into_iter()
+            .filter(|worktree| self.includes_worktree(&worktree.name))
+            .collect();
         let mut file_modifications: HashMap<String, Vec<String>> = HashMap::new();
-        let mut file_overlaps = Vec::new();
+        let categories = if self.filter.include_untracked {
+            self.filter.change_categories
+        } else {
+            self.filter.change_categories.without(ChangeCategories::UNTRACKED)
+        };
+
+        // Collect modified files from all worktrees. Each call opens its own repo
+        // handle, so worktrees can be scanned in parallel. Paths are normalized so the
+        // same file matches across worktrees whatever separators git reported.
+        let modified_by_worktree = worktrees
+            .par_iter()
+            .map(|worktree| -> Result<(String, BTreeSet<String>, BTreeSet<String>)> {
+                let (excluded, modified_files): (BTreeSet<String>, BTreeSet<String>) = self.git_manager
+                    .get_modified_files(&worktree.name, categories)?
+                    .iter()
+                    .map(|file_path| to_repo_relative(Path::new(file_path)))
+                    .filter(|file_path| !self.ignore_set.is_match(file_path))
+                    .partition(|file_path| self.ignore_file.is_ignored(file_path));
+                Ok((worktree.name.clone(), modified_files, excluded))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        // Collect modified files from all worktrees
-        for worktree in &worktrees {
-            let modified_files = self.git_manager.get_modified_files(&worktree.name)?;
-            
+        let mut excluded_files = BTreeSet::new();
+        for (worktree_name, modified_files, excluded) in modified_by_worktree {
+            excluded_files.extend(excluded);
             for file_path in modified_files {
                 file_modifications.entry(file_path)
-                    .or_insert_with(Vec::new)
-                    .push(worktree.name.clone());
+                    .or_default()
+                    .push(worktree_name.clone());
             }
         }
 
         // Find files modified in multiple worktrees
-        for (file_path, worktree_names) in file_modifications {
-            if worktree_names.len() > 1 {
-                let overlap_info = self.analyze_file_overlap(&file_path, &worktree_names)?;
-                file_overlaps.push(overlap_info);
-            }
-        }
+        let mut file_overlaps = file_modifications
+            .into_par_iter()
+            .filter(|(_, worktree_names)| worktree_names.len() > 1)
+            .map(|(file_path, worktree_names)| self.analyze_file_overlap(&file_path, &worktree_names))
+            .collect::<Result<Vec<_>>>()?;
 
-        // Calculate risk assessment
-        let mut low_risk = 0;
-        let mut medium_risk = 0;
-        let mut high_risk = 0;
+        // Keep the output stable between runs
+        file_overlaps.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        // Calculate risk assessment, overall and per extension
+        let mut risk_assessment = RiskAssessment::default();
+        let mut by_extension: HashMap<String, RiskAssessment> = HashMap::new();
 
         for overlap in &file_overlaps {
-            match overlap.conflict_risk.as_str() {
-                "low" => low_risk += 1,
-                "medium" => medium_risk += 1,
-                "high" => high_risk += 1,
-                _ => {}
-            }
+            let extension = Path::new(&overlap.file_path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("(none)")
+                .to_string();
+            risk_assessment.record(&overlap.conflict_risk);
+            by_extension.entry(extension).or_default().record(&overlap.conflict_risk);
         }
 
-        let risk_assessment = RiskAssessment {
-            low: low_risk,
-            medium: medium_risk,
-            high: high_risk,
-        };
-
         // Generate recommendations
         let recommendations = self.generate_recommendations(&file_overlaps);
 
+        let mut worktree_involvement: HashMap<String, usize> = HashMap::new();
+        for overlap in &file_overlaps {
+            for worktree_name in &overlap.worktrees {
+                *worktree_involvement.entry(worktree_name.clone()).or_insert(0) += 1;
+            }
+        }
+        let hottest_worktree = worktree_involvement.iter()
+            .max_by(|(name_a, count_a), (name_b, count_b)| count_a.cmp(count_b).then(name_b.cmp(name_a)))
+            .map(|(name, _)| name.clone());
+
         Ok(OverlapAnalysisResult {
             total_overlaps: file_overlaps.len(),
             file_overlaps,
             risk_assessment,
             recommendations,
+            worktree_involvement,
+            hottest_worktree,
+            excluded_files: excluded_files.len(),
+            by_extension,
         })
     }
 
+    /// Returns the cached analysis when no worktree has changed since the last run.
+    pub fn analyze_overlaps_cached(&self, cache: &OverlapCache, force_refresh: bool) -> Result<OverlapAnalysisResult> {
+        let fingerprint = self.worktrees_fingerprint()?;
+
+        if !force_refresh {
+            let entries = cache.entries.lock().unwrap();
+            if let Some((cached_fingerprint, result)) = entries.get(&self.repo_path) {
+                if *cached_fingerprint == fingerprint {
+                    return Ok(result.clone());
+                }
+            }
+        }
+
+        let result = self.analyze_overlaps()?;
+        cache.entries.lock().unwrap().insert(self.repo_path.clone(), (fingerprint, result.clone()));
+
+        Ok(result)
+    }
+
+    fn includes_worktree(&self, name: &str) -> bool {
+        match &self.filter.worktree_names {
+            Some(worktree_names) => worktree_names.iter().any(|selected| selected == name),
+            None => true,
+        }
+    }
+
+    /// Hashes each worktree's HEAD sha and working-tree status, including the mtime of
+    /// dirty files so repeated edits to an already-modified file are noticed.
+    fn worktrees_fingerprint(&self) -> Result<u64> {
+        let repo = self.git_manager.open_repo()?;
+        let mut worktree_names: Vec<String> = repo.worktrees()?
+            .iter()
+            .flatten()
+            .filter(|name| self.includes_worktree(name))
+            .map(str::to_string)
+            .collect();
+        worktree_names.sort();
+
+        let mut hasher = DefaultHasher::new();
+        self.filter.include_untracked.hash(&mut hasher);
+        self.filter.change_categories.hash(&mut hasher);
+        self.ignore_file.lines.hash(&mut hasher);
+        self.filter.ignore_patterns.hash(&mut hasher);
+        self.filter.worktree_names.hash(&mut hasher);
+        serde_json::to_string(&self.risk_config)?.hash(&mut hasher);
+        for worktree_name in &worktree_names {
+            worktree_name.hash(&mut hasher);
+
+            let worktree_repo = match self.git_manager.open_worktree_repo(worktree_name) {
+                Ok(worktree_repo) => worktree_repo,
+                Err(_) => continue,
+            };
+            if let Some(head_oid) = worktree_repo.head().ok().and_then(|head| head.target()) {
+                head_oid.as_bytes().hash(&mut hasher);
+            }
+
+            // Ignored files (build output and the like) never take part in the analysis
+            let mut status_options = StatusOptions::new();
+            status_options.include_ignored(false).include_untracked(true);
+            let workdir = worktree_repo.workdir().map(Path::to_path_buf);
+            for entry in worktree_repo.statuses(Some(&mut status_options))?.iter() {
+                let path = entry.path().unwrap_or_default().to_string();
+                path.hash(&mut hasher);
+                entry.status().bits().hash(&mut hasher);
+
+                let modified_time = workdir.as_ref()
+                    .and_then(|workdir| std::fs::metadata(workdir.join(&path)).ok())
+                    .and_then(|metadata| metadata.modified().ok());
+                modified_time.hash(&mut hasher);
+            }
+        }
+
+        Ok(hasher.finish())
+    }
+
     fn analyze_file_overlap(&self, file_path: &str, worktree_names: &[String]) -> Result<FileOverlapInfo> {
         let mut last_modified = HashMap::new();
         let mut line_changes = HashMap::new();
         let mut total_changes = 0;
+        // Symbol -> number of worktrees touching it, while every worktree's version parses
+        let mut symbol_counts: Option<BTreeMap<String, usize>> = Some(BTreeMap::new());
+        let mut deleted_in = 0;
+        let merge_base = self.common_merge_base(worktree_names)?;
+        let mut contents: Vec<Option<Vec<u8>>> = Vec::new();
 
         for worktree_name in worktree_names {
-            let worktree_path = self.repo_path.join("worktrees").join(worktree_name);
+            let worktree_path = self.git_manager.worktree_path(worktree_name)?;
             let full_file_path = worktree_path.join(file_path);
 
             if full_file_path.exists() {
@@ -140,15 +583,68 @@ impl OverlapAnalyzer {
                     last_modified.insert(worktree_name.clone(), datetime);
                 }
 
-                // Analyze line changes (simplified - in real implementation, use git diff)
-                let line_change_info = self.analyze_line_changes(&full_file_path)?;
-                total_changes += line_change_info.lines_added + line_change_info.lines_removed + line_change_info.lines_modified;
+                // Analyze line changes against the ancestor shared by all the worktrees
+                let line_change_info = self.analyze_line_changes(worktree_name, file_path, merge_base)?;
+                total_changes += if line_change_info.is_binary {
+                    BINARY_CHANGE_WEIGHT
+                } else {
+                    line_change_info.lines_added + line_change_info.lines_removed + line_change_info.lines_modified
+                };
+
+                let touched = Self::symbols_touched(&full_file_path, &line_change_info);
+                symbol_counts = symbol_counts.zip(touched).map(|(mut counts, touched)| {
+                    for symbol in touched {
+                        *counts.entry(symbol).or_insert(0) += 1;
+                    }
+                    counts
+                });
+
+                line_changes.insert(worktree_name.clone(), line_change_info);
+                contents.push(std::fs::read(&full_file_path).ok());
+            } else {
+                // Deleted in this worktree; the diff shows every line as removed
+                let mut line_change_info = self.analyze_line_changes(worktree_name, file_path, merge_base)?;
+                line_change_info.is_deleted = true;
+                total_changes += line_change_info.lines_removed;
+                symbol_counts = None;
+                deleted_in += 1;
                 line_changes.insert(worktree_name.clone(), line_change_info);
+                contents.push(None);
             }
         }
 
+        // Worktrees that made the same change merge cleanly however much they changed
+        let identical_changes = deleted_in == 0
+            && contents.iter().all(|content| content.is_some())
+            && contents.windows(2).all(|pair| pair[0] == pair[1]);
+
         // Determine conflict risk based on changes and file type
-        let conflict_risk = self.assess_conflict_risk(file_path, total_changes, worktree_names.len());
+        let mut conflict_risk = self.assess_conflict_risk(file_path, total_changes, worktree_names.len());
+
+        let symbol_overlaps: Vec<String> = symbol_counts.as_ref()
+            .map(|counts| {
+                counts.iter()
+                    .filter(|(_, count)| **count > 1)
+                    .map(|(symbol, _)| symbol.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Worktrees editing different functions of the same file rarely conflict
+        if symbol_counts.is_some() && symbol_overlaps.is_empty() && conflict_risk == "high" {
+            conflict_risk = "medium".to_string();
+        }
+
+        let any_binary = line_changes.values().any(|line_change_info| line_change_info.is_binary);
+        let auto_mergeable = identical_changes
+            || (deleted_in == 0 && !any_binary && self.merges_cleanly(file_path, worktree_names)?);
+
+        // Changes to a file another worktree deleted can't merge cleanly
+        if deleted_in > 0 && deleted_in < worktree_names.len() {
+            conflict_risk = "high".to_string();
+        } else if identical_changes || deleted_in == worktree_names.len() {
+            conflict_risk = "low".to_string();
+        }
 
         Ok(FileOverlapInfo {
             file_path: file_path.to_string(),
@@ -156,30 +652,171 @@ impl OverlapAnalyzer {
             conflict_risk,
             last_modified,
             line_changes,
+            symbol_overlaps,
+            merge_base: merge_base.map(|oid| oid.to_string()),
+            identical_changes,
+            auto_mergeable,
         })
     }
 
-    fn analyze_line_changes(&self, file_path: &Path) -> Result<LineChangeInfo> {
-        // Simplified implementation - in reality, you'd use git diff
-        // For now, we'll use basic file analysis
-        
-        let content = std::fs::read_to_string(file_path)?;
-        let lines = content.lines().count();
-        
-        // Mock change analysis - in real implementation, use git diff
-        let change_regions = vec![
-            ChangeRegion {
-                start_line: 1,
-                end_line: lines.min(10),
-                change_type: "modified".to_string(),
+    /// Whether every pair of worktrees' versions of `file_path` merges without conflicts.
+    /// Previews merge in memory, so this leaves no objects behind in the repository.
+    fn merges_cleanly(&self, file_path: &str, worktree_names: &[String]) -> Result<bool> {
+        for (index, worktree_a) in worktree_names.iter().enumerate() {
+            for worktree_b in &worktree_names[index + 1..] {
+                let preview = self.preview_merge(file_path, worktree_a, worktree_b)?;
+                if preview.has_conflicts || !preview.conflict_regions.is_empty() {
+                    return Ok(false);
+                }
             }
-        ];
+        }
+        Ok(true)
+    }
+
+    /// The merge-base of two worktrees' HEADs, or `None` if they share no history.
+    pub fn merge_base(&self, worktree_a: &str, worktree_b: &str) -> Result<Option<Oid>> {
+        let repo = self.git_manager.open_repo()?;
+        let head_a = self.git_manager.open_worktree_repo(worktree_a)?.head()?.peel_to_commit()?.id();
+        let head_b = self.git_manager.open_worktree_repo(worktree_b)?.head()?.peel_to_commit()?.id();
+
+        match repo.merge_base(head_a, head_b) {
+            Ok(oid) => Ok(Some(oid)),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The merge-base shared by all of `worktree_names`, folding in one worktree at a time.
+    fn common_merge_base(&self, worktree_names: &[String]) -> Result<Option<Oid>> {
+        let (first, second) = match worktree_names {
+            [first, second, ..] => (first, second),
+            _ => return Ok(None),
+        };
+        let mut base = match self.merge_base(first, second)? {
+            Some(base) => base,
+            None => return Ok(None),
+        };
+
+        let repo = self.git_manager.open_repo()?;
+        for worktree_name in &worktree_names[2..] {
+            let head = self.git_manager.open_worktree_repo(worktree_name)?.head()?.peel_to_commit()?.id();
+            base = match repo.merge_base(base, head) {
+                Ok(oid) => oid,
+                Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+        }
+
+        Ok(Some(base))
+    }
+
+    /// Symbols enclosing a worktree's changes to a file, or `None` if that can't be told.
+    fn symbols_touched(full_file_path: &Path, line_change_info: &LineChangeInfo) -> Option<BTreeSet<String>> {
+        if line_change_info.is_binary {
+            return None;
+        }
+
+        let content = std::fs::read_to_string(full_file_path).ok()?;
+        let ranges: Vec<(usize, usize)> = line_change_info.change_regions
+            .iter()
+            .map(|region| (region.start_line, region.end_line))
+            .collect();
+        symbol_parser::symbols_touched(full_file_path, &content, &ranges)
+    }
+
+    /// Diffs a worktree's copy of `file_path` against `base`, or against the point where
+    /// the worktree diverged from main when there is no shared base.
+    fn analyze_line_changes(&self, worktree_name: &str, file_path: &str, base: Option<Oid>) -> Result<LineChangeInfo> {
+        let worktree_repo = self.git_manager.open_worktree_repo(worktree_name)?;
+
+        let base_oid = match base {
+            Some(base_oid) => base_oid,
+            None => {
+                let main_repo = self.git_manager.open_repo()?;
+                let main_commit = main_repo.head()?.peel_to_commit()?;
+                let worktree_commit = worktree_repo.head()?.peel_to_commit()?;
+                worktree_repo.merge_base(worktree_commit.id(), main_commit.id())?
+            }
+        };
+        let base_tree = worktree_repo.find_commit(base_oid)?.tree()?;
+
+        let mut diff_options = DiffOptions::new();
+        diff_options
+            .pathspec(file_path)
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true)
+            .context_lines(0);
+        let diff = worktree_repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_options))?;
+
+        let mut lines_added = 0;
+        let mut lines_removed = 0;
+        let mut lines_modified = 0;
+        let mut change_regions = Vec::new();
+        let mut is_binary = false;
+
+        for delta_index in 0..diff.deltas().len() {
+            let patch = match Patch::from_diff(&diff, delta_index)? {
+                Some(patch) => patch,
+                None => {
+                    // git2 yields no patch for binary deltas; record that it changed
+                    // without trying to read line information
+                    let changed = diff.get_delta(delta_index)
+                        .is_some_and(|delta| delta.status() != Delta::Unmodified);
+                    if changed {
+                        is_binary = true;
+                        change_regions.push(ChangeRegion {
+                            start_line: 0,
+                            end_line: 0,
+                            change_type: "binary".to_string(),
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index)?;
+                let mut hunk_added = 0;
+                let mut hunk_removed = 0;
+
+                for line_index in 0..line_count {
+                    match patch.line_in_hunk(hunk_index, line_index)?.origin() {
+                        '+' => hunk_added += 1,
+                        '-' => hunk_removed += 1,
+                        _ => {}
+                    }
+                }
+
+                // Paired additions and removals count as modifications
+                let hunk_modified = hunk_added.min(hunk_removed);
+                lines_modified += hunk_modified;
+                lines_added += hunk_added - hunk_modified;
+                lines_removed += hunk_removed - hunk_modified;
+
+                let change_type = match (hunk.old_lines(), hunk.new_lines()) {
+                    (0, _) => "added",
+                    (_, 0) => "removed",
+                    _ => "modified",
+                };
+                let start_line = hunk.new_start() as usize;
+                let end_line = start_line + (hunk.new_lines() as usize).saturating_sub(1);
+
+                change_regions.push(ChangeRegion {
+                    start_line,
+                    end_line,
+                    change_type: change_type.to_string(),
+                });
+            }
+        }
 
         Ok(LineChangeInfo {
-            lines_added: lines / 10,  // Mock data
-            lines_removed: lines / 20, // Mock data
-            lines_modified: lines / 15, // Mock data
+            lines_added,
+            lines_removed,
+            lines_modified,
             change_regions,
+            is_binary,
+            is_deleted: false,
         })
     }
 
@@ -190,159 +827,667 @@ impl OverlapAnalyzer {
             .and_then(|ext| ext.to_str())
             .unwrap_or("");
 
-        let base_risk = match file_extension {
-            "rs" | "ts" | "js" | "py" | "go" | "java" => 2, // Code files have higher risk
-            "json" | "yaml" | "toml" | "xml" => 3,          // Config files have highest risk
-            "md" | "txt" => 1,                              // Documentation has lower risk
-            _ => 2,
-        };
+        let config = &self.risk_config;
+        let base_risk = config.extension_weights
+            .get(file_extension)
+            .copied()
+            .unwrap_or(config.default_extension_weight);
 
-        let change_risk = if total_changes > 100 { 2 } else if total_changes > 50 { 1 } else { 0 };
-        let worktree_risk = if worktree_count > 3 { 2 } else if worktree_count > 2 { 1 } else { 0 };
+        let change_risk = if total_changes > config.high_change_threshold {
+            2
+        } else if total_changes > config.medium_change_threshold {
+            1
+        } else {
+            0
+        };
+        let worktree_risk = if worktree_count > config.high_worktree_threshold {
+            2
+        } else if worktree_count > config.medium_worktree_threshold {
+            1
+        } else {
+            0
+        };
 
         let total_risk = base_risk + change_risk + worktree_risk;
 
-        match total_risk {
-            0..=3 => "low".to_string(),
-            4..=6 => "medium".to_string(),
-            _ => "high".to_string(),
+        if total_risk <= config.low_cutoff {
+            "low".to_string()
+        } else if total_risk <= config.medium_cutoff {
+            "medium".to_string()
+        } else {
+            "high".to_string()
         }
     }
 
-    fn generate_recommendations(&self, file_overlaps: &[FileOverlapInfo]) -> Vec<String> {
+    fn generate_recommendations(&self, file_overlaps: &[FileOverlapInfo]) -> Vec<Recommendation> {
+        use RecommendationCode as Code;
+        use RecommendationSeverity as Severity;
+
         let mut recommendations = Vec::new();
 
         if file_overlaps.is_empty() {
-            recommendations.push("No file overlaps detected. All worktrees are working on separate files.".to_string());
+            recommendations.push(Recommendation::new(
+                Severity::Info,
+                Code::NoOverlaps,
+                "No file overlaps detected. All worktrees are working on separate files.".to_string(),
+                Vec::new(),
+            ));
             return recommendations;
         }
 
-        let high_risk_count = file_overlaps.iter().filter(|o| o.conflict_risk == "high").count();
-        let medium_risk_count = file_overlaps.iter().filter(|o| o.conflict_risk == "medium").count();
+        let files_where = |predicate: &dyn Fn(&FileOverlapInfo) -> bool| -> Vec<String> {
+            file_overlaps.iter()
+                .filter(|o| predicate(o))
+                .map(|o| o.file_path.clone())
+                .collect()
+        };
+        let high_risk_files = files_where(&|o| o.conflict_risk == "high");
+        let medium_risk_files = files_where(&|o| o.conflict_risk == "medium");
+        let auto_mergeable_files = files_where(&|o| o.auto_mergeable);
+
+        if !high_risk_files.is_empty() {
+            recommendations.push(Recommendation::new(
+                Severity::Critical,
+                Code::HighRiskFiles,
+                format!(
+                    "{} files have high conflict risk. Consider coordinating changes or merging frequently.",
+                    high_risk_files.len()
+                ),
+                high_risk_files,
+            ));
+        }
 
-        if high_risk_count > 0 {
-            recommendations.push(format!(
-                "⚠️ {} files have high conflict risk. Consider coordinating changes or merging frequently.",
-                high_risk_count
+        if !medium_risk_files.is_empty() {
+            recommendations.push(Recommendation::new(
+                Severity::Warning,
+                Code::MediumRiskFiles,
+                format!(
+                    "{} files have medium conflict risk. Review changes before merging.",
+                    medium_risk_files.len()
+                ),
+                medium_risk_files,
             ));
         }
 
-        if medium_risk_count > 0 {
-            recommendations.push(format!(
-                "⚡ {} files have medium conflict risk. Review changes before merging.",
-                medium_risk_count
+        if !auto_mergeable_files.is_empty() {
+            recommendations.push(Recommendation::new(
+                Severity::Info,
+                Code::AutoMergeable,
+                format!("{} files can be auto-merged without conflicts.", auto_mergeable_files.len()),
+                auto_mergeable_files,
             ));
         }
 
         // Find most problematic files
-        let mut high_risk_files: Vec<&FileOverlapInfo> = file_overlaps
+        let mut high_risk_overlaps: Vec<&FileOverlapInfo> = file_overlaps
             .iter()
             .filter(|o| o.conflict_risk == "high")
             .collect();
-        high_risk_files.sort_by(|a, b| b.worktrees.len().cmp(&a.worktrees.len()));
+        high_risk_overlaps.sort_by_key(|overlap| std::cmp::Reverse(overlap.worktrees.len()));
 
-        if let Some(most_problematic) = high_risk_files.first() {
-            recommendations.push(format!(
-                "🔥 Most problematic file: {} (modified in {} worktrees)",
-                most_problematic.file_path,
-                most_problematic.worktrees.len()
+        if let Some(most_problematic) = high_risk_overlaps.first() {
+            recommendations.push(Recommendation::new(
+                Severity::Critical,
+                Code::MostProblematicFile,
+                format!(
+                    "Most problematic file: {} (modified in {} worktrees)",
+                    most_problematic.file_path,
+                    most_problematic.worktrees.len()
+                ),
+                vec![most_problematic.file_path.clone()],
             ));
         }
 
         // Suggest coordination strategies
         if file_overlaps.len() > 5 {
-            recommendations.push(
-                "💡 Consider splitting work into smaller, more focused branches to reduce overlap."
-                    .to_string(),
-            );
+            recommendations.push(Recommendation::new(
+                Severity::Info,
+                Code::SplitBranches,
+                "Consider splitting work into smaller, more focused branches to reduce overlap.".to_string(),
+                Vec::new(),
+            ));
         }
 
-        if file_overlaps.iter().any(|o| o.worktrees.len() > 3) {
-            recommendations.push(
-                "🤝 Some files are being modified in 3+ worktrees. Consider designating ownership."
-                    .to_string(),
-            );
+        let widely_shared_files = files_where(&|o| o.worktrees.len() > 3);
+        if !widely_shared_files.is_empty() {
+            recommendations.push(Recommendation::new(
+                Severity::Warning,
+                Code::DesignateOwnership,
+                "Some files are being modified in 3+ worktrees. Consider designating ownership.".to_string(),
+                widely_shared_files,
+            ));
         }
 
         recommendations
     }
 
-    pub fn analyze_dependencies(&self, file_paths: &[String]) -> Result<Vec<DependencyInfo>> {
+    /// Parses and resolves the files in parallel, then works out dependents from the
+    /// combined results. Files and skipped entries are sorted by path.
+    pub fn analyze_dependencies(&self, file_paths: &[String]) -> Result<DependencyAnalysis> {
+        // Many files share imports; each one is resolved against the file system once
+        let resolution_cache = ResolutionCache::default();
+
+        let outcomes: Vec<Option<Result<DependencyInfo, SkippedFile>>> = file_paths
+            .par_iter()
+            .map(|file_path| {
+                let relative_path = normalize_path(Path::new(file_path));
+                if !self.repo_path.join(&relative_path).exists() {
+                    return None;
+                }
+                if self.ignore_file.is_ignored(&to_repo_relative(&relative_path)) {
+                    return Some(Err(SkippedFile {
+                        file_path: file_path.clone(),
+                        reason: format!("Excluded by {}", IGNORE_FILE_NAME),
+                    }));
+                }
+
+                // One unreadable file shouldn't cost the results for the rest
+                Some(self.analyze_file_dependencies(&relative_path, &resolution_cache).map_err(|e| SkippedFile {
+                    file_path: file_path.clone(),
+                    reason: e.to_string(),
+                }))
+            })
+            .collect();
+
         let mut dependencies = Vec::new();
+        let mut skipped = Vec::new();
+        for outcome in outcomes.into_iter().flatten() {
+            match outcome {
+                Ok(dep_info) => dependencies.push(dep_info),
+                Err(skipped_file) => skipped.push(skipped_file),
+            }
+        }
+        dependencies.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        skipped.sort_by(|a, b| a.file_path.cmp(&b.file_path));
 
-        for file_path in file_paths {
-            let full_path = self.repo_path.join(file_path);
-            if full_path.exists() {
-                let dep_info = self.analyze_file_dependencies(&full_path)?;
-                dependencies.push(dep_info);
+        // Invert the import graph to find who depends on each file
+        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        for dep_info in &dependencies {
+            for resolved in dep_info.dependencies.iter().filter_map(|dep| dep.resolved_path.as_ref()) {
+                let importers = dependents.entry(resolved.clone()).or_default();
+                if !importers.contains(&dep_info.file_path) {
+                    importers.push(dep_info.file_path.clone());
+                }
             }
         }
 
-        Ok(dependencies)
+        for dep_info in &mut dependencies {
+            if let Some(mut file_dependents) = dependents.remove(&dep_info.file_path) {
+                file_dependents.sort();
+                // Files that many others rely on have a wider blast radius
+                dep_info.impact_score += file_dependents.len() as f64;
+                dep_info.dependents = file_dependents;
+            }
+        }
+
+        Ok(DependencyAnalysis { files: dependencies, skipped })
     }
 
-    fn analyze_file_dependencies(&self, file_path: &Path) -> Result<DependencyInfo> {
-        // Simplified dependency analysis
-        // In a real implementation, you'd use tree-sitter or other AST parsers
-        
-        let content = std::fs::read_to_string(file_path)?;
-        let mut dependencies = Vec::new();
-        
-        // Look for import statements (simplified)
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("import ") || trimmed.starts_with("use ") || trimmed.starts_with("require(") {
-                // Extract dependency path (very simplified)
-                if let Some(dep) = self.extract_dependency_path(trimmed) {
-                    dependencies.push(dep);
+    /// Maps an import of `importer` (repo-relative) to the repo-relative path of the file
+    /// it refers to. Only relative JS/TS/Python imports and Rust `mod`/`crate::`/`self::`/
+    /// `super::` paths can be resolved; anything else comes from a package.
+    fn resolve_dependency(&self, importer: &Path, dependency: &str, cache: &ResolutionCache) -> Option<String> {
+        // Relative imports depend only on the importer's directory; Rust paths also on
+        // whether the importer is a crate root or `mod.rs`
+        let is_rust = importer.extension().is_some_and(|ext| ext == "rs");
+        let context = if is_rust {
+            importer.to_path_buf()
+        } else {
+            importer.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let key = (context, dependency.to_string());
+        if let Some(resolved) = cache.lock().unwrap().get(&key) {
+            return resolved.clone();
+        }
+
+        let resolved = self.resolve_dependency_uncached(importer, dependency);
+        cache.lock().unwrap().insert(key, resolved.clone());
+        resolved
+    }
+
+    fn resolve_dependency_uncached(&self, importer: &Path, dependency: &str) -> Option<String> {
+        let is_rust = importer.extension().is_some_and(|ext| ext == "rs");
+        let resolved = if is_rust {
+            self.resolve_rust_dependency(importer, dependency)
+        } else if dependency.starts_with('.') {
+            let base = normalize_path(&importer.parent()?.join(dependency));
+            let candidates = std::iter::once(base.clone())
+                .chain(RESOLVABLE_EXTENSIONS.iter().map(|ext| append_extension(&base, ext)))
+                .chain(RESOLVABLE_EXTENSIONS.iter().map(|ext| base.join(format!("index.{}", ext))));
+            self.first_existing(candidates)
+        } else {
+            None
+        }?;
+
+        Some(to_repo_relative(&resolved))
+    }
+
+    /// Rust paths are resolved to the file of the deepest module that exists, so
+    /// `crate::a::b::Item` maps to `a/b.rs` (or `a/b/mod.rs`), falling back to `a.rs`.
+    fn resolve_rust_dependency(&self, importer: &Path, dependency: &str) -> Option<PathBuf> {
+        let mut segments: Vec<&str> = dependency.split("::").collect();
+        let mut module_dir = rust_module_dir(importer);
+
+        match segments.first().copied() {
+            Some("crate") => {
+                module_dir = self.rust_crate_root(importer)?;
+                segments.remove(0);
+            }
+            Some("self") => {
+                segments.remove(0);
+            }
+            Some("super") => {
+                while segments.first() == Some(&"super") {
+                    module_dir = module_dir.parent()?.to_path_buf();
+                    segments.remove(0);
                 }
             }
+            // `mod foo;` declarations are bare names of child modules
+            Some(_) if segments.len() == 1 => {}
+            _ => return None,
+        }
+
+        for depth in (1..=segments.len()).rev() {
+            let module = segments[..depth].iter().fold(module_dir.clone(), |path, segment| path.join(segment));
+            let candidates = [module.with_extension("rs"), module.join("mod.rs")];
+            if let Some(found) = self.first_existing(candidates.into_iter()) {
+                return Some(found);
+            }
+        }
+
+        None
+    }
+
+    /// The directory holding `lib.rs`/`main.rs` for the crate `importer` belongs to.
+    fn rust_crate_root(&self, importer: &Path) -> Option<PathBuf> {
+        importer.ancestors().skip(1).find(|dir| {
+            ["lib.rs", "main.rs"].iter().any(|root| self.repo_path.join(dir).join(root).is_file())
+        }).map(Path::to_path_buf)
+    }
+
+    fn first_existing(&self, mut candidates: impl Iterator<Item = PathBuf>) -> Option<PathBuf> {
+        candidates.find(|candidate| self.repo_path.join(candidate).is_file())
+    }
+
+    fn analyze_file_dependencies(&self, file_path: &Path, resolution_cache: &ResolutionCache) -> Result<DependencyInfo> {
+        let full_path = self.repo_path.join(file_path);
+        let size = std::fs::metadata(&full_path)?.len();
+        if size > self.max_dependency_file_bytes {
+            return Err(anyhow!("File is {} bytes, over the {} byte limit", size, self.max_dependency_file_bytes));
         }
 
+        let content = String::from_utf8(std::fs::read(&full_path)?)
+            .map_err(|_| anyhow!("File is not valid UTF-8"))?;
+        let dependencies: Vec<Dependency> = import_parser::extract_dependencies(file_path, &content)
+            .into_iter()
+            .map(|raw| Dependency {
+                resolved_path: self.resolve_dependency(file_path, &raw, resolution_cache),
+                raw,
+            })
+            .collect();
+
         // Calculate impact score based on number of dependencies and file size
         let impact_score = (dependencies.len() as f64) * 0.5 + (content.lines().count() as f64) * 0.1;
 
         Ok(DependencyInfo {
-            file_path: file_path.to_string_lossy().to_string(),
+            file_path: to_repo_relative(file_path),
             dependencies,
-            dependents: Vec::new(), // Would need cross-reference analysis
+            dependents: Vec::new(), // Filled in by analyze_dependencies
             impact_score,
         })
     }
 
-    fn extract_dependency_path(&self, line: &str) -> Option<String> {
-        // Very simplified dependency extraction
-        // In reality, you'd use proper parsers
-        
-        if line.contains("\"") {
-            let parts: Vec<&str> = line.split('"').collect();
-            if parts.len() >= 2 {
-                return Some(parts[1].to_string());
+    /// Three-way merges the working-tree versions of `file_path` in two worktrees, using
+    /// the file at the merge-base of their HEADs as the ancestor. Nothing is written to
+    /// either worktree or to the repository; the merge inputs only live in memory.
+    pub fn preview_merge(&self, file_path: &str, worktree_a: &str, worktree_b: &str) -> Result<MergePreview> {
+        let repo = self.git_manager.open_repo()?;
+        let repo_a = self.git_manager.open_worktree_repo(worktree_a)?;
+        let repo_b = self.git_manager.open_worktree_repo(worktree_b)?;
+
+        let head_a = repo_a.head()?.peel_to_commit()?.id();
+        let head_b = repo_b.head()?.peel_to_commit()?.id();
+
+        // Without shared history, fall back to an empty ancestor (a two-way merge)
+        let (ancestor_content, has_common_ancestor) = match repo.merge_base(head_a, head_b) {
+            Ok(base_oid) => {
+                let base_tree = repo.find_commit(base_oid)?.tree()?;
+                let content = match base_tree.get_path(Path::new(file_path)) {
+                    Ok(entry) => repo.find_blob(entry.id())?.content().to_vec(),
+                    Err(e) if e.code() == git2::ErrorCode::NotFound => Vec::new(), // Added on both sides
+                    Err(e) => return Err(e.into()),
+                };
+                (content, true)
+            }
+            Err(e) if e.code() == git2::ErrorCode::NotFound => (Vec::new(), false),
+            Err(e) => return Err(e.into()),
+        };
+
+        let read_side = |worktree_name: &str| -> Result<Vec<u8>> {
+            let path = self.git_manager.worktree_path(worktree_name)?.join(file_path);
+            match std::fs::read(&path) {
+                Ok(content) => Ok(content),
+                // Deleted or never created on this side
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+                Err(e) => Err(anyhow!("Failed to read '{}': {}", path.display(), e)),
             }
+        };
+
+        let ours_content = read_side(worktree_a)?;
+        let theirs_content = read_side(worktree_b)?;
+
+        // With at most one side changed, that side is the merge. This also keeps deletions
+        // away from git2, whose merge result can't hold empty content
+        if ours_content == ancestor_content || theirs_content == ancestor_content || ours_content == theirs_content {
+            let merged = if ours_content == ancestor_content { theirs_content } else { ours_content };
+            let content = String::from_utf8_lossy(&merged).to_string();
+            return Ok(MergePreview {
+                file_path: file_path.to_string(),
+                has_conflicts: false,
+                has_common_ancestor,
+                conflict_regions: find_conflict_regions(&content),
+                content,
+            });
         }
-        
-        if line.contains("'") {
-            let parts: Vec<&str> = line.split('\'').collect();
-            if parts.len() >= 2 {
-                return Some(parts[1].to_string());
+
+        let scratch = in_memory_repo()?;
+        let ancestor = blob_index_entry(&scratch, file_path, &ancestor_content)?;
+        let ours = blob_index_entry(&scratch, file_path, &ours_content)?;
+        let theirs = blob_index_entry(&scratch, file_path, &theirs_content)?;
+
+        let mut merge_options = MergeFileOptions::new();
+        merge_options
+            .ancestor_label("base")
+            .our_label(worktree_a)
+            .their_label(worktree_b)
+            .style_diff3(true);
+        let merged = scratch.merge_file_from_index(&ancestor, &ours, &theirs, Some(&mut merge_options))?;
+
+        let content = String::from_utf8_lossy(merged.content()).to_string();
+        let conflict_regions = find_conflict_regions(&content);
+
+        Ok(MergePreview {
+            file_path: file_path.to_string(),
+            has_conflicts: !merged.is_automergeable(),
+            has_common_ancestor,
+            conflict_regions,
+            content,
+        })
+    }
+}
+
+/// A repository whose only object database is in memory. `merge_file_from_index` reads
+/// its inputs as blobs, and writing them here keeps them out of `.git/objects`.
+fn in_memory_repo() -> Result<Repository> {
+    let odb = git2::Odb::new()?;
+    odb.add_new_mempack_backend(1)?;
+    Ok(Repository::from_odb(odb)?)
+}
+
+/// Writes `content` to `repo`'s object database and describes it as an index entry,
+/// which is what `merge_file_from_index` takes.
+fn blob_index_entry(repo: &Repository, path: &str, content: &[u8]) -> Result<IndexEntry> {
+    let path = path.as_bytes().to_vec();
+    Ok(IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: content.len() as u32,
+        id: repo.blob(content)?,
+        flags: path.len().min(0xfff) as u16,
+        flags_extended: 0,
+        path,
+    })
+}
+
+/// Finds `<<<<<<<` ... `>>>>>>>` blocks in merged content.
+fn find_conflict_regions(content: &str) -> Vec<ConflictRegion> {
+    let mut regions = Vec::new();
+    let mut start = None;
+
+    for (index, line) in content.lines().enumerate() {
+        if line.starts_with("<<<<<<<") {
+            start = Some(index + 1);
+        } else if line.starts_with(">>>>>>>") {
+            if let Some(start_line) = start.take() {
+                regions.push(ConflictRegion { start_line, end_line: index + 1 });
             }
         }
+    }
 
-        None
+    regions
+}
+
+/// Appends `.ext` without replacing an existing extension, so `./api.client` becomes
+/// `./api.client.ts` rather than `./api.ts`.
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Directory holding the child modules of the module defined in `file`: the file's own
+/// directory for `lib.rs`, `main.rs` and `mod.rs`, otherwise a directory named after it.
+fn rust_module_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new("")).to_path_buf();
+    match file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("lib" | "main" | "mod") | None => parent,
+        Some(stem) => parent.join(stem),
     }
 }
 
+/// Formats a repo-relative path the same way on every platform: forward slashes, with
+/// `.` and `..` components resolved.
+fn to_repo_relative(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    normalize_path(Path::new(&path)).to_string_lossy().replace('\\', "/")
+}
+
+/// Lexically resolves `.` and `..` components without touching the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
 #[tauri::command]
-pub async fn analyze_worktree_overlaps(repo_path: String) -> Result<OverlapAnalysisResult, String> {
-    let analyzer = OverlapAnalyzer::new(repo_path).map_err(|e| e.to_string())?;
-    analyzer.analyze_overlaps().map_err(|e| e.to_string())
+pub async fn analyze_worktree_overlaps(
+    cache: State<'_, OverlapCache>,
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    force_refresh: Option<bool>,
+    filter: Option<OverlapFilterConfig>,
+    risk_config: Option<RiskConfig>,
+    worktree_names: Option<Vec<String>>,
+) -> Result<OverlapAnalysisResult, WhiplashError> {
+    let mut filter = filter.unwrap_or_default();
+    if worktree_names.is_some() {
+        filter.worktree_names = worktree_names;
+    }
+
+    // The analysis fans out over rayon and does blocking git work, so keep it off the runtime
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    let cache = cache.inner().clone();
+    run_blocking(timeout, move || {
+        OverlapAnalyzer::new(repo_path)?
+            .with_filter(filter)?
+            .with_risk_config(risk_config.unwrap_or_default())
+            .analyze_overlaps_cached(&cache, force_refresh.unwrap_or(false))
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn analyze_file_dependencies(
+    timeouts: State<'_, GitTimeoutConfig>,
     repo_path: String,
     file_paths: Vec<String>,
-) -> Result<Vec<DependencyInfo>, String> {
-    let analyzer = OverlapAnalyzer::new(repo_path).map_err(|e| e.to_string())?;
-    analyzer.analyze_dependencies(&file_paths).map_err(|e| e.to_string())
-}
\ No newline at end of file
+    max_file_size: Option<u64>,
+) -> Result<DependencyAnalysis, WhiplashError> {
+    // Parsing fans out over rayon, so keep it off the runtime like the overlap analysis
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        OverlapAnalyzer::new(repo_path)?
+            .with_max_dependency_file_size(max_file_size.unwrap_or(DEFAULT_MAX_DEPENDENCY_FILE_BYTES))
+            .analyze_dependencies(&file_paths)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn preview_merge(
+    timeouts: State<'_, GitTimeoutConfig>,
+    repo_path: String,
+    file_path: String,
+    worktree_a: String,
+    worktree_b: String,
+) -> Result<MergePreview, WhiplashError> {
+    let timeout = Duration::from_secs(timeouts.operation_timeout_seconds);
+    run_blocking(timeout, move || {
+        OverlapAnalyzer::new(repo_path)?.preview_merge(&file_path, &worktree_a, &worktree_b)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture_repo, write_files};
+
+    #[test]
+    fn line_changes_match_a_known_diff() {
+        let (dir, _repo) = fixture_repo(&[("notes.txt", "one\ntwo\nthree\nfour\nfive\n")]);
+        let worktree = GitWorktreeManager::new(dir.path()).unwrap()
+            .create_worktree("feature", "feature", None, true, None, false, None)
+            .unwrap();
+        write_files(Path::new(&worktree.path), &[("notes.txt", "one\nTWO\nthree\nfour\nfive\nsix\nseven\n")]);
+
+        let analyzer = OverlapAnalyzer::new(dir.path()).unwrap();
+        let changes = analyzer.analyze_line_changes("feature", "notes.txt", None).unwrap();
+
+        assert_eq!((changes.lines_added, changes.lines_removed, changes.lines_modified), (2, 0, 1));
+        assert!(!changes.is_binary);
+        let regions: Vec<_> = changes.change_regions.iter()
+            .map(|region| (region.start_line, region.end_line, region.change_type.as_str()))
+            .collect();
+        assert_eq!(regions, [(2, 2, "modified"), (6, 7, "added")]);
+    }
+
+    #[test]
+    fn overlaps_across_many_files_are_sorted_by_path() {
+        let files: Vec<(String, String)> = (0..200)
+            .map(|index| (format!("src/module_{:03}.rs", index), format!("fn f() -> u32 {{ {} }}\n", index)))
+            .collect();
+        let file_refs: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        let (dir, _repo) = fixture_repo(&file_refs);
+
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+        for name in ["alpha", "beta", "gamma"] {
+            let worktree = manager.create_worktree(name, name, None, true, None, false, None).unwrap();
+            // Every worktree touches the even-numbered modules; only alpha and beta touch the rest
+            let edits: Vec<(&str, &str)> = file_refs.iter()
+                .filter(|(path, _)| name != "gamma" || path.trim_end_matches(".rs").ends_with(['0', '2', '4', '6', '8']))
+                .map(|(path, _)| (*path, "fn f() -> u32 { u32::MAX }\n"))
+                .collect();
+            write_files(Path::new(&worktree.path), &edits);
+        }
+
+        let analyzer = OverlapAnalyzer::new(dir.path()).unwrap();
+        let result = analyzer.analyze_overlaps().unwrap();
+
+        assert_eq!(result.total_overlaps, 200);
+        let paths: Vec<_> = result.file_overlaps.iter().map(|overlap| overlap.file_path.clone()).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+        let shared_by_all = result.file_overlaps.iter().filter(|overlap| overlap.worktrees.len() == 3).count();
+        assert_eq!(shared_by_all, 100);
+
+        let rerun: Vec<_> = analyzer.analyze_overlaps().unwrap().file_overlaps.into_iter().map(|overlap| overlap.file_path).collect();
+        assert_eq!(rerun, paths);
+    }
+
+    #[test]
+    fn merge_previews_treat_only_missing_files_as_empty() {
+        let (dir, _repo) = fixture_repo(&[("notes.txt", "one\n"), ("shared/a.txt", "a\n")]);
+        let manager = GitWorktreeManager::new(dir.path()).unwrap();
+        let alpha = manager.create_worktree("alpha", "alpha", None, true, None, false, None).unwrap();
+        let beta = manager.create_worktree("beta", "beta", None, true, None, false, None).unwrap();
+        let analyzer = OverlapAnalyzer::new(dir.path()).unwrap();
+
+        std::fs::remove_file(Path::new(&alpha.path).join("notes.txt")).unwrap();
+        let preview = analyzer.preview_merge("notes.txt", "alpha", "beta").unwrap();
+        assert!(!preview.has_conflicts);
+
+        // A directory where the file should be can't be read, and mustn't look deleted
+        std::fs::remove_file(Path::new(&beta.path).join("notes.txt")).unwrap();
+        std::fs::create_dir(Path::new(&beta.path).join("notes.txt")).unwrap();
+        assert!(analyzer.preview_merge("notes.txt", "alpha", "beta").is_err());
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture dependency_analysis_speedup`.
+    #[test]
+    #[ignore]
+    fn dependency_analysis_speedup() {
+        let (dir, _repo) = fixture_repo(&[("README.md", "bench\n")]);
+        let files: Vec<(String, String)> = (0..2000)
+            .map(|index| {
+                let imports: String = (1..=8)
+                    .map(|offset| format!("import {{ value{0} }} from './module_{0}';\n", (index + offset * 97) % 2000))
+                    .collect();
+                (format!("src/module_{}.ts", index), format!("{}export const value{} = {};\n", imports, index, index))
+            })
+            .collect();
+        let file_refs: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        write_files(dir.path(), &file_refs);
+        let file_paths: Vec<String> = files.iter().map(|(path, _)| path.clone()).collect();
+
+        let analyzer = OverlapAnalyzer::new(dir.path()).unwrap();
+        let timed = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let started = std::time::Instant::now();
+            let analysis = pool.install(|| analyzer.analyze_dependencies(&file_paths)).unwrap();
+            (started.elapsed(), analysis)
+        };
+
+        let (serial_time, serial) = timed(1);
+        let threads = rayon::current_num_threads();
+        let (parallel_time, parallel) = timed(threads);
+        println!(
+            "{} files: 1 thread {:?}, {} threads {:?} ({:.1}x)",
+            file_paths.len(),
+            serial_time,
+            threads,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+
+        let summary = |analysis: &DependencyAnalysis| -> Vec<(String, usize, Vec<String>)> {
+            analysis.files.iter()
+                .map(|file| (file.file_path.clone(), file.dependencies.len(), file.dependents.clone()))
+                .collect()
+        };
+        assert_eq!(summary(&serial), summary(&parallel));
+        assert!(parallel.files.windows(2).all(|pair| pair[0].file_path < pair[1].file_path));
+        assert!(parallel.files.iter().all(|file| file.dependents.len() == 8));
+    }
+
+    #[test]
+    fn repo_relative_paths_normalize_mixed_separators() {
+        assert_eq!(to_repo_relative(Path::new("src\\ui/components\\button.ts")), "src/ui/components/button.ts");
+        assert_eq!(to_repo_relative(Path::new("./src\\ui/../lib\\.\\index.ts")), "src/lib/index.ts");
+    }
+}
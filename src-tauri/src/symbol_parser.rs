@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+/// Reported for changes that fall outside every function, type or module.
+pub const TOP_LEVEL: &str = "<top-level>";
+
+/// A named definition and the lines (1-based, inclusive) it spans.
+struct Symbol {
+    name: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// Maps changed line ranges (1-based, inclusive) to the innermost symbols enclosing them,
+/// e.g. `Parser::parse` or `App.render`. Returns `None` when the file's language isn't
+/// supported or it can't be parsed, so callers can fall back to file-level analysis.
+pub fn symbols_touched(file_path: &Path, content: &str, ranges: &[(usize, usize)]) -> Option<BTreeSet<String>> {
+    let (language, separator) = language_for(file_path)?;
+
+    let mut parser = Parser::new();
+    parser.set_language(language).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let mut symbols = Vec::new();
+    collect_symbols(tree.root_node(), content, separator, &mut Vec::new(), &mut symbols);
+
+    let mut touched = BTreeSet::new();
+    for &(start, end) in ranges {
+        let overlapping: Vec<&Symbol> = symbols.iter()
+            .filter(|symbol| symbol.start_line <= end && symbol.end_line >= start)
+            .collect();
+
+        // Keep only the innermost symbols; their parents are implied by the name
+        let innermost: Vec<&Symbol> = overlapping.iter()
+            .filter(|symbol| {
+                let prefix = format!("{}{}", symbol.name, separator);
+                !overlapping.iter().any(|other| other.name.starts_with(&prefix))
+            })
+            .copied()
+            .collect();
+
+        if innermost.is_empty() {
+            touched.insert(TOP_LEVEL.to_string());
+        }
+        touched.extend(innermost.into_iter().map(|symbol| symbol.name.clone()));
+    }
+
+    Some(touched)
+}
+
+fn language_for(file_path: &Path) -> Option<(Language, &'static str)> {
+    let extension = file_path.extension().and_then(|ext| ext.to_str())?;
+    match extension {
+        "rs" => Some((tree_sitter_rust::language(), "::")),
+        "ts" => Some((tree_sitter_typescript::language_typescript(), ".")),
+        "tsx" => Some((tree_sitter_typescript::language_tsx(), ".")),
+        "js" | "jsx" | "mjs" | "cjs" => Some((tree_sitter_javascript::language(), ".")),
+        _ => None,
+    }
+}
+
+fn collect_symbols(node: Node, source: &str, separator: &str, parents: &mut Vec<String>, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match symbol_name(child, source) {
+            Some(name) => {
+                parents.push(name);
+                symbols.push(Symbol {
+                    name: parents.join(separator),
+                    start_line: child.start_position().row + 1,
+                    end_line: child.end_position().row + 1,
+                });
+                collect_symbols(child, source, separator, parents, symbols);
+                parents.pop();
+            }
+            None => collect_symbols(child, source, separator, parents, symbols),
+        }
+    }
+}
+
+/// The name of a node that defines a symbol, or `None` for any other node.
+fn symbol_name(node: Node, source: &str) -> Option<String> {
+    let name_node = match node.kind() {
+        // Rust
+        "function_item" | "struct_item" | "enum_item" | "union_item" | "trait_item" | "mod_item"
+        | "macro_definition" => node.child_by_field_name("name"),
+        "impl_item" => node.child_by_field_name("type"),
+        // JavaScript / TypeScript
+        "function_declaration" | "generator_function_declaration" | "class_declaration"
+        | "abstract_class_declaration" | "method_definition" | "interface_declaration"
+        | "type_alias_declaration" | "enum_declaration" => node.child_by_field_name("name"),
+        // `const handler = () => { ... }`
+        "variable_declarator" => {
+            let value = node.child_by_field_name("value")?;
+            match value.kind() {
+                "arrow_function" | "function" | "function_expression" => node.child_by_field_name("name"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }?;
+
+    name_node.utf8_text(source.as_bytes()).ok().map(str::to_string)
+}
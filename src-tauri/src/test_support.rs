@@ -0,0 +1,44 @@
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A repository in a temp dir with `files` committed on `main`. The repo has a user
+/// configured, so code that commits through git config works.
+pub fn fixture_repo(files: &[(&str, &str)]) -> (TempDir, Repository) {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init_opts(dir.path(), RepositoryInitOptions::new().initial_head("main")).unwrap();
+    {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Whiplash Test").unwrap();
+        config.set_str("user.email", "test@whiplash.invalid").unwrap();
+    }
+    commit_files(&repo, files, "Initial commit");
+    (dir, repo)
+}
+
+/// Writes `files` into the repo's working directory and commits them on HEAD.
+pub fn commit_files(repo: &Repository, files: &[(&str, &str)], message: &str) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    write_files(workdir, files);
+
+    let mut index = repo.index().unwrap();
+    for (path, _) in files {
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let signature = Signature::now("Whiplash Test", "test@whiplash.invalid").unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+}
+
+/// Writes `files` under `root`, creating parent directories as needed.
+pub fn write_files(root: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use crate::error::WhiplashError;
+use crate::git_worktree::GitWorktreeManager;
+use anyhow::{Result, anyhow};
+use tauri::{AppHandle, State};
+use tauri_plugin_opener::OpenerExt;
+
+/// Where `open_worktree_in` opens a worktree.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenTarget {
+    Terminal,
+    Editor,
+    FileManager,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenerConfig {
+    /// Editor to launch, with any arguments, e.g. `code -n`. Falls back to `$EDITOR`,
+    /// then `code`.
+    pub editor_command: Option<String>,
+}
+
+impl OpenerConfig {
+    fn editor_command(&self) -> String {
+        self.editor_command
+            .clone()
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|command| !command.trim().is_empty())
+            .unwrap_or_else(|| "code".to_string())
+    }
+}
+
+/// Opens a worktree's directory in a terminal, an editor or the system file manager.
+pub fn open_worktree(app: &AppHandle, config: &OpenerConfig, repo_path: &str, name: &str, target: OpenTarget) -> Result<()> {
+    let path = GitWorktreeManager::new(repo_path)?.worktree_path(name)?;
+    if !path.is_dir() {
+        return Err(WhiplashError::NotFound(format!(
+            "Worktree '{}' no longer exists at '{}'",
+            name,
+            path.display()
+        )).into());
+    }
+
+    match target {
+        OpenTarget::Editor => {
+            let command = config.editor_command();
+            let mut parts = command.split_whitespace();
+            let program = parts.next().ok_or_else(|| anyhow!("Editor command is empty"))?;
+            Command::new(program)
+                .args(parts)
+                .arg(&path)
+                .current_dir(&path)
+                .spawn()
+                .map_err(|e| anyhow!("Failed to launch editor '{}': {}", program, e))?;
+        }
+        OpenTarget::Terminal => open_terminal(&path)?,
+        OpenTarget::FileManager => {
+            app.opener().open_path(path.to_string_lossy(), None::<&str>)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn open_terminal(path: &Path) -> Result<()> {
+    Command::new("open").args(["-a", "Terminal"]).arg(path).spawn()?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn open_terminal(path: &Path) -> Result<()> {
+    Command::new("cmd").args(["/C", "start", "cmd"]).current_dir(path).spawn()?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn open_terminal(path: &Path) -> Result<()> {
+    // There's no standard terminal on Linux, so try the common ones in turn
+    for terminal in ["x-terminal-emulator", "gnome-terminal", "konsole", "xfce4-terminal", "xterm"] {
+        if Command::new(terminal).current_dir(path).spawn().is_ok() {
+            return Ok(());
+        }
+    }
+    Err(anyhow!("No terminal emulator found"))
+}
+
+#[tauri::command]
+pub async fn open_worktree_in(
+    app: AppHandle,
+    config: State<'_, OpenerConfig>,
+    repo_path: String,
+    name: String,
+    target: OpenTarget,
+) -> Result<(), WhiplashError> {
+    open_worktree(&app, &config, &repo_path, &name, target).map_err(WhiplashError::from)
+}
@@ -0,0 +1,116 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Component, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::error::WhiplashError;
+use crate::git_worktree::{GitWorktreeManager, WHIPLASH_DIR};
+use anyhow::{Result, anyhow};
+use tauri::{AppHandle, Emitter, State};
+
+/// Changes are batched for this long after the first one, so a burst of saves produces
+/// a single event per worktree.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Payload of the `worktree-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeChangedEvent {
+    pub worktree_name: String,
+}
+
+/// Watches every worktree of a repo for file changes. Only one repo is watched at a time.
+#[derive(Default)]
+pub struct WorktreeWatcher {
+    // Dropping the watcher closes its event channel, which ends the debounce thread
+    active: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl WorktreeWatcher {
+    /// Starts watching the worktrees of `repo_path`, replacing any previous watcher.
+    /// Returns the names of the watched worktrees.
+    pub fn start(&self, app: &AppHandle, repo_path: &str) -> Result<Vec<String>> {
+        let manager = GitWorktreeManager::new(repo_path)?;
+        let repo = manager.open_repo()?;
+
+        let mut worktrees: Vec<(String, PathBuf)> = Vec::new();
+        for name in repo.worktrees()?.iter().flatten() {
+            let path = manager.worktree_path(name)?;
+            if path.exists() {
+                worktrees.push((name.to_string(), path));
+            }
+        }
+
+        let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for (_, path) in &worktrees {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        }
+
+        let app = app.clone();
+        let watched = worktrees.clone();
+        std::thread::spawn(move || {
+            let mut pending = BTreeSet::new();
+            let mut first_change: Option<Instant> = None;
+            loop {
+                // Flush DEBOUNCE after the first change even if changes keep coming, so a
+                // constantly written file can't hold events back forever
+                let wait = first_change
+                    .map(|first_change| DEBOUNCE.saturating_sub(first_change.elapsed()))
+                    .unwrap_or(Duration::from_secs(3600));
+
+                match rx.recv_timeout(wait) {
+                    Ok(Ok(event)) => {
+                        for path in &event.paths {
+                            // Skip git's own bookkeeping, e.g. index.lock churn, and whiplash's
+                            // task logs, which are written on every line of output
+                            let internal = path.components().any(|component| {
+                                component == Component::Normal(".git".as_ref())
+                                    || component == Component::Normal(WHIPLASH_DIR.as_ref())
+                            });
+                            if internal {
+                                continue;
+                            }
+                            if let Some((name, _)) = watched.iter().find(|(_, root)| path.starts_with(root)) {
+                                pending.insert(name.clone());
+                                first_change.get_or_insert_with(Instant::now);
+                            }
+                        }
+                    }
+                    Ok(Err(_)) => {}
+                    Err(RecvTimeoutError::Timeout) => {
+                        for worktree_name in std::mem::take(&mut pending) {
+                            let _ = app.emit("worktree-changed", WorktreeChangedEvent { worktree_name });
+                        }
+                        first_change = None;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        *self.active.lock().map_err(|_| anyhow!("Watcher lock poisoned"))? = Some(watcher);
+        Ok(worktrees.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Stops watching. Returns whether a watcher was running.
+    pub fn stop(&self) -> Result<bool> {
+        let watcher = self.active.lock().map_err(|_| anyhow!("Watcher lock poisoned"))?.take();
+        Ok(watcher.is_some())
+    }
+}
+
+#[tauri::command]
+pub async fn start_worktree_watcher(
+    app: AppHandle,
+    watcher: State<'_, WorktreeWatcher>,
+    repo_path: String,
+) -> Result<Vec<String>, WhiplashError> {
+    watcher.start(&app, &repo_path).map_err(WhiplashError::from)
+}
+
+#[tauri::command]
+pub async fn stop_worktree_watcher(watcher: State<'_, WorktreeWatcher>) -> Result<bool, WhiplashError> {
+    watcher.stop().map_err(WhiplashError::from)
+}
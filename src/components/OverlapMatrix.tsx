@@ -103,7 +103,7 @@ export const OverlapMatrix: React.FC<OverlapMatrixProps> = ({ repoPath }) => {
             {overlapAnalysis.recommendations.map((recommendation, index) => (
               <li key={index} className="text-blue-800 flex items-start gap-2">
                 <span className="text-blue-500 mt-1">•</span>
-                <span>{recommendation}</span>
+                <span>{recommendation.message}</span>
               </li>
             ))}
           </ul>
@@ -22,9 +22,9 @@ export function useClaudeCode(): UseClaudeCodeResult {
     try {
       setLoading(true);
       setError(null);
-      const result = await invoke<any[]>('list_claude_tasks');
+      const result = await invoke<{ tasks: any[]; total: number }>('list_claude_tasks', { includeOutput: true });
       
-      const mappedTasks: ClaudeTask[] = result.map((task: any) => ({
+      const mappedTasks: ClaudeTask[] = result.tasks.map((task: any) => ({
         id: task.id,
         description: task.description,
         status: task.status as 'pending' | 'running' | 'completed' | 'failed',
@@ -41,7 +41,12 @@ export function useOverlapAnalysis(repoPath: string): UseOverlapAnalysisResult {
           medium: result.risk_assessment.medium,
           high: result.risk_assessment.high,
         },
-        recommendations: result.recommendations,
+        recommendations: result.recommendations.map((recommendation: any) => ({
+          severity: recommendation.severity,
+          code: recommendation.code,
+          message: recommendation.message,
+          relatedFiles: recommendation.related_files,
+        })),
       };
       
       setOverlapAnalysis(mappedAnalysis);
@@ -56,14 +61,14 @@ export function useOverlapAnalysis(repoPath: string): UseOverlapAnalysisResult {
     try {
       setLoading(true);
       setError(null);
-      const result = await invoke<any[]>('analyze_file_dependencies', { repoPath, filePaths });
+      const result = await invoke<any>('analyze_file_dependencies', { repoPath, filePaths });
       
-      const mappedDependencies: DependencyNode[] = result.map((dep: any) => ({
+      const mappedDependencies: DependencyNode[] = result.files.map((dep: any) => ({
         id: dep.file_path,
         name: dep.file_path.split('/').pop() || dep.file_path,
         type: 'file' as const,
         path: dep.file_path,
-        dependencies: dep.dependencies,
+        dependencies: dep.dependencies.map((d: any) => d.resolved_path ?? d.raw),
         dependents: dep.dependents,
         impactScore: dep.impact_score,
       }));
@@ -73,7 +73,21 @@ export interface OverlapAnalysis {
     medium: number;
     high: number;
   };
-  recommendations: string[];
+  recommendations: Recommendation[];
+}
+
+export interface Recommendation {
+  severity: 'info' | 'warning' | 'critical';
+  code:
+    | 'no_overlaps'
+    | 'high_risk_files'
+    | 'medium_risk_files'
+    | 'auto_mergeable'
+    | 'most_problematic_file'
+    | 'split_branches'
+    | 'designate_ownership';
+  message: string;
+  relatedFiles: string[];
 }
 
 export interface TauriCommand<T = any> {
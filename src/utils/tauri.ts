@@ -97,9 +97,24 @@ const mockOverlapAnalysis: OverlapAnalysis = {
     high: 1,
   },
   recommendations: [
-    '⚠️ 1 files have high conflict risk. Consider coordinating changes or merging frequently.',
-    '⚡ 1 files have medium conflict risk. Review changes before merging.',
-    '🔥 Most problematic file: types/index.ts (modified in 2 worktrees)',
+    {
+      severity: 'critical',
+      code: 'high_risk_files',
+      message: '1 files have high conflict risk. Consider coordinating changes or merging frequently.',
+      relatedFiles: ['types/index.ts'],
+    },
+    {
+      severity: 'warning',
+      code: 'medium_risk_files',
+      message: '1 files have medium conflict risk. Review changes before merging.',
+      relatedFiles: ['package.json'],
+    },
+    {
+      severity: 'critical',
+      code: 'most_problematic_file',
+      message: 'Most problematic file: types/index.ts (modified in 2 worktrees)',
+      relatedFiles: ['types/index.ts'],
+    },
   ],
 };
 
@@ -148,13 +163,16 @@ export async function invoke<T>(command: string, args?: any): Promise<T> {
       return undefined as T;
 
     case 'get_worktree_status':
-      return ['modified: src/App.tsx', 'new: src/components/NewComponent.tsx'] as T;
+      return [
+        { path: 'src/App.tsx', states: ['modified'] },
+        { path: 'src/components/NewComponent.tsx', states: ['new'] },
+      ] as T;
 
     case 'get_modified_files':
       return ['src/App.tsx', 'src/components/NewComponent.tsx', 'types/index.ts'] as T;
 
     case 'list_claude_tasks':
-      return mockTasks as T;
+      return { tasks: mockTasks, total: mockTasks.length, paused: false } as T;
 
     case 'start_claude_task':
       const newTask: ClaudeTask = {
@@ -208,20 +226,26 @@ export async function invoke<T>(command: string, args?: any): Promise<T> {
       return mockOverlapAnalysis as T;
 
     case 'analyze_file_dependencies':
-      return [
-        {
-          file_path: 'src/App.tsx',
-          dependencies: ['./components/Header', './hooks/useAuth'],
-          dependents: [],
-          impact_score: 8.5,
-        },
-        {
-          file_path: 'types/index.ts',
-          dependencies: [],
-          dependents: ['src/App.tsx', 'src/components/UserList.tsx'],
-          impact_score: 9.2,
-        },
-      ] as T;
+      return {
+        files: [
+          {
+            file_path: 'src/App.tsx',
+            dependencies: [
+              { raw: './components/Header', resolved_path: 'src/components/Header.tsx' },
+              { raw: './hooks/useAuth', resolved_path: 'src/hooks/useAuth.ts' },
+            ],
+            dependents: [],
+            impact_score: 8.5,
+          },
+          {
+            file_path: 'types/index.ts',
+            dependencies: [],
+            dependents: ['src/App.tsx', 'src/components/UserList.tsx'],
+            impact_score: 9.2,
+          },
+        ],
+        skipped: [],
+      } as T;
 
     default:
       console.warn(`Mock implementation not found for command: ${command}`);